@@ -26,7 +26,7 @@ fn main() {
     for _ in 0..1000 {
         network.learn_batch(data.clone(), 0.1);
         println!("cost: {}", data.iter().map(|x| {
-            CrossEntropy::cost(&network.forward(x.0.clone()).0,&x.1)
+            network.cost.cost(&network.infer(x.0.clone()),&x.1)
         }).sum::<f32>());
     }
 }
@@ -2,12 +2,11 @@ use std::time::Instant;
 
 use raylib::prelude::*;
 
-#[allow(unused_imports)]
-use convoluted::{activation::sigmoid::Sigmoid, array::Array1D, cost::CrossEntropy, layer::{bias::BiasLayer, convolution::Convolution, dense::DenseLayer, pooling::MaxPooling, reshape::{Flatten, Shape}, LayerChain}};
+use convoluted::{activation::softmax::Softmax, array::Array1D, layer::{Layer, Mode}};
 use serde::{Deserialize, Serialize};
 
-type Network = convoluted::Network<Array1D<{ 28*28 }>, LayerChain<LayerChain<LayerChain<LayerChain<DenseLayer<{ 28*28 }, 100>, (), Array1D<{ 28*28 }>>, Sigmoid, Array1D<{ 28*28 }>>, DenseLayer<100, 10>, Array1D<{ 28*28 }>>, Sigmoid, Array1D<{ 28*28 }>>, CrossEntropy, Array1D<10>, usize>;
-// type Network = convoluted::Network<Array1D<{ 28*28 }>, LayerChain<LayerChain<LayerChain<LayerChain<LayerChain<LayerChain<LayerChain<LayerChain<LayerChain<LayerChain<LayerChain<LayerChain<LayerChain<Shape<784, 28, 28>, (), Array1D<784>>, Convolution<5>, Array1D<784>>, BiasLayer<28, 28>, Array1D<784>>, Sigmoid, Array1D<784>>, MaxPooling<2, 14, 14>, Array1D<784>>, Convolution<3>, Array1D<784>>, BiasLayer<14, 14>, Array1D<784>>, Sigmoid, Array1D<784>>, Flatten<{ 14*14 }, 14, 14>, Array1D<784>>, DenseLayer<{ 14*14 }, 64>, Array1D<784>>, Sigmoid, Array1D<784>>, DenseLayer<64, 10>, Array1D<784>>, Sigmoid, Array1D<784>>, CrossEntropy, Array1D<10>, usize>;
+type Network = mnist::DenseNetwork;
+// type Network = mnist::ConvolutionNetwork;
 
 const PIXEL_SIZE: usize = 20;
 
@@ -26,7 +25,8 @@ impl Default for Config {
 
 fn main() {
     let config = load_cfg();
-    let network = Network::load("network_dense.bin").unwrap();
+    let mut network = Network::load("network_dense.bin").unwrap();
+    network.set_mode(Mode::Inference);
     let (mut rl, rt) = init()
         .title("MNIST classifier")
         .size(0, 0)
@@ -53,7 +53,7 @@ fn main() {
             drawing_area = Array1D::new();
         }
 
-        let result = network.forward(drawing_area.clone()).0;
+        let result = Softmax.infer(network.infer(drawing_area.clone()));
         let mut d = rl.begin_drawing(&rt);
         d.clear_background(Color::new(16, 16, 16, 255));
         
@@ -76,9 +76,8 @@ fn main() {
             if config.sorted {
                 sorted.sort_by(|x, y| y.1.partial_cmp(x.1).unwrap());
             }
-            let sum: f32 = sorted.iter().map(|x| x.1).sum();
             for (i, (number, chance)) in sorted.iter().enumerate() {
-                let chance = **chance / sum;
+                let chance = **chance;
                 d.draw_text(&format!("{}: {:.2}%", number, chance * 100.0), width as i32 / 2 + 14 * PIXEL_SIZE as i32 + 20, 40 * i as i32 + height as i32 / 2 - 40 * 5 + 5, 30, Color::WHITE);
                 if config.chance_bars {
                     d.draw_rectangle(width as i32 / 2 + 14 * PIXEL_SIZE as i32 + 20 + 150, 40 * i as i32 + height as i32 / 2 - 40 * 5 + 5, 100, 20, Color::GRAY);
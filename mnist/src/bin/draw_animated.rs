@@ -1,9 +1,11 @@
 use raylib::prelude::*;
 
-use convoluted::{activation::sigmoid::Sigmoid, array::{Array1D, Array2D}, cost::CrossEntropy, layer::{bias::BiasLayer, convolution::Convolution, dense::DenseLayer, pooling::MaxPooling, reshape::{Flatten, Shape}, Layer, LayerChain}};
+use std::any::Any;
+
+use convoluted::array::{Array1D, Array2D};
 use serde::{Deserialize, Serialize};
 
-type Network = convoluted::Network<Array1D<{ 28*28 }>, LayerChain<LayerChain<LayerChain<LayerChain<LayerChain<LayerChain<LayerChain<LayerChain<LayerChain<LayerChain<LayerChain<LayerChain<LayerChain<Shape<784, 28, 28>, (), Array1D<784>>, Convolution<5>, Array1D<784>>, BiasLayer<28, 28>, Array1D<784>>, Sigmoid, Array1D<784>>, MaxPooling<2, 14, 14>, Array1D<784>>, Convolution<3>, Array1D<784>>, BiasLayer<14, 14>, Array1D<784>>, Sigmoid, Array1D<784>>, Flatten<{ 14*14 }, 14, 14>, Array1D<784>>, DenseLayer<{ 14*14 }, 64>, Array1D<784>>, Sigmoid, Array1D<784>>, DenseLayer<64, 10>, Array1D<784>>, Sigmoid, Array1D<784>>, CrossEntropy, Array1D<10>, usize>;
+type Network = mnist::ConvolutionNetwork;
 
 const PIXEL_SIZE: usize = 20;
 
@@ -29,22 +31,6 @@ fn main() {
         .fullscreen()
         .build();
 
-    let layer = network.into_layer();
-    let layers = (
-        layer.step.step.step.step.step.step.step.step.step.step.step.step.step,
-        layer.step.step.step.step.step.step.step.step.step.step.step.next,
-        layer.step.step.step.step.step.step.step.step.step.step.next,
-        layer.step.step.step.step.step.step.step.step.step.next,
-        layer.step.step.step.step.step.step.step.step.next,
-        layer.step.step.step.step.step.step.step.next,
-        layer.step.step.step.step.step.step.next,
-        layer.step.step.step.step.step.next,
-        layer.step.step.step.step.next,
-        layer.step.step.step.next,
-        layer.step.step.next,
-        layer.step.next,
-        layer.next
-    );
     let mut step = 0usize;
 
     let width = rl.get_screen_width() as usize;
@@ -53,10 +39,11 @@ fn main() {
     rl.set_target_fps(60);
 
     let mut drawing_area = Array1D::<{ 28*28 }>::new();
-    let mut shaped_area = Array2D::<28, 28>::new();
-    let mut pooled_area = Array2D::<14, 14>::new();
-    let mut dense_area_1 = Array1D::<64>::new();
-    let mut dense_area_2 = Array1D::<10>::new();
+    // outputs of every layer, grouped by shape
+    let mut shaped_areas = Vec::<Array2D<28, 28>>::new();
+    let mut pooled_areas = Vec::<Array2D<14, 14>>::new();
+    let mut dense_areas_1 = Vec::<Array1D<64>>::new();
+    let mut dense_areas_2 = Vec::<Array1D<10>>::new();
     while !rl.window_should_close() {
         if rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT) && step == 0 {
             let mut pos = rl.get_mouse_position();
@@ -72,45 +59,22 @@ fn main() {
         if rl.is_key_pressed(KeyboardKey::KEY_SPACE) {
             step += 1;
 
-            match step {
-                1 => {
-                    shaped_area = layers.0.forward(drawing_area.clone()).0;
-                    shaped_area = layers.1.forward(shaped_area).0;
-                }
-                2 => {
-                    shaped_area = layers.2.forward(shaped_area).0;
-                }
-                3 => {
-                    shaped_area = layers.3.forward(shaped_area).0;
-                }
-                4 => {
-                    pooled_area = layers.4.forward(shaped_area.clone()).0;
-                }
-                5 => {
-                    pooled_area = layers.5.forward(pooled_area).0;
-                }
-                6 => {
-                    pooled_area = layers.6.forward(pooled_area).0;
-                }
-                7 => {
-                    pooled_area = layers.7.forward(pooled_area).0;
-                }
-                8 => {
-                    let flat_area = layers.8.forward(pooled_area.clone()).0;
-                    dense_area_1 = layers.9.forward(flat_area).0;
-                }
-                9 => {
-                    dense_area_1 = layers.10.forward(dense_area_1).0;
-                }
-                10 => {
-                    dense_area_2 = layers.11.forward(dense_area_1.clone()).0;
-                }
-                11 => {
-                    dense_area_2 = layers.12.forward(dense_area_2).0;
-                }
-                _ => {
-                    step = 0;
-                }
+            if step == 1 {
+                (shaped_areas, pooled_areas, dense_areas_1, dense_areas_2) = Default::default();
+                network.forward_traced(drawing_area.clone(), &mut |_, output: &dyn Any| {
+                    if let Some(output) = output.downcast_ref::<Array2D<28, 28>>() {
+                        shaped_areas.push(output.clone());
+                    } else if let Some(output) = output.downcast_ref::<Array2D<14, 14>>() {
+                        pooled_areas.push(output.clone());
+                    } else if let Some(output) = output.downcast_ref::<Array1D<64>>() {
+                        dense_areas_1.push(output.clone());
+                    } else if let Some(output) = output.downcast_ref::<Array1D<10>>() {
+                        dense_areas_2.push(output.clone());
+                    }
+                });
+            }
+            if step > 11 {
+                step = 0;
             }
         }
 
@@ -121,22 +85,23 @@ fn main() {
             0 => {
                 draw_1d_array::<{ 28*28 }, 28, 28>(&mut d, &drawing_area, width, height);        
             }
+            // the first of these is the reshaped input
             1..=3 => {
-                draw_2d_array(&mut d, &shaped_area, width, height);
+                draw_2d_array(&mut d, &shaped_areas[step], width, height);
             }
             4..=7 => {
-                draw_2d_array(&mut d, &pooled_area, width, height);
+                draw_2d_array(&mut d, &pooled_areas[step - 4], width, height);
             }
             8..=9 => {
-                draw_1d_array::<{ 8*8 }, 8, 8>(&mut d, &dense_area_1, width, height);
+                draw_1d_array::<{ 8*8 }, 8, 8>(&mut d, &dense_areas_1[step - 8], width, height);
             }
             10..=11 => {
-                draw_1d_array::<10, 5, 2>(&mut d, &dense_area_2, width, height);
+                draw_1d_array::<10, 5, 2>(&mut d, &dense_areas_2[step - 10], width, height);
             }
             _ => {}
         }
         if step == 11 {
-            let sorted: Vec<_> = dense_area_2.iter().enumerate().collect();
+            let sorted: Vec<_> = dense_areas_2[1].iter().enumerate().collect();
             let sum: f32 = sorted.iter().map(|x| x.1).sum();
             for (i, (number, chance)) in sorted[5..].iter().enumerate() {
                 d.draw_line(width as i32/2 - (1.5*PIXEL_SIZE as f32) as i32 + (i*PIXEL_SIZE) as i32, height as i32/2 + PIXEL_SIZE as i32, 150 * i as i32 + width as i32 / 2 - (150 * 2), height as i32 / 2 - 20 + 250, Color::WHITE);
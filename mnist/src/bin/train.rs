@@ -2,27 +2,30 @@ use std::io::Write;
 use std::time::Instant;
 
 use convoluted::activation::sigmoid::Sigmoid;
-use convoluted::array::Array1D;
-use convoluted::cost::{CostFunction, CrossEntropy};
-use convoluted::layer::{dense::DenseLayer, LayerChain};
-use convoluted::Network;
-use rand::{rng, seq::SliceRandom};
+use convoluted::cost::CostFunction;
+use convoluted::initializer::Initializer;
+use convoluted::layer::{dense::DenseLayer, dropout::Dropout, Mode};
+use convoluted::sequential;
+use mnist::DenseNetwork;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
 
 fn main() {
-    let mut network = Network::<Array1D<{ 28*28 }>, _, CrossEntropy, Array1D<10>, _>::new(
-        LayerChain::new(DenseLayer::<{ 28*28 }, 100>::random(), ())
-            .push(Sigmoid::new())
-            .push(DenseLayer::<100, 10>::random())
-            .push(Sigmoid::new())
-    );
-    let (input, labels) = mnist::get_mnist_train();
+    // seeded, so runs can be reproduced
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut network = DenseNetwork::new(sequential![
+        DenseLayer::with_initializer_and_rng(Initializer::XavierUniform, &mut rng),
+        Sigmoid::new(),
+        Dropout::seeded(0),
+        DenseLayer::with_initializer_and_rng(Initializer::XavierUniform, &mut rng),
+    ]);
+    let (input, labels) = mnist::get_mnist_train(&mut rng);
     let mut data: Vec<_> = input.into_iter().zip(labels).collect();
     let (test_input, test_labels) = mnist::get_mnist_test();
-    let mut rng = rng();
     for x in 0..10 {
         println!("Epoch {}/10", x+1);
         let start_time = Instant::now();
         data.shuffle(&mut rng);
+        network.set_mode(Mode::Training);
         for (i, chunk) in data.chunks(10).enumerate() {
             network.learn_batch(chunk.to_owned(), 1.0);
             if i % 89 == 0 || i == 5999 {
@@ -32,14 +35,15 @@ fn main() {
         }
         println!();
         println!("Epoch time: {:.03}", start_time.elapsed().as_secs_f64());
+        network.set_mode(Mode::Inference);
         let mut cost = 0.0;
         let mut correct = 0;
         for (input, label) in test_input.iter().zip(&test_labels) {
-            let out = network.forward(input.clone()).0;
+            let out = network.infer(input.clone());
             if out.iter().enumerate().max_by(|(_, x), (_, y)| {x.partial_cmp(y).unwrap()}).unwrap().0 == *label {
                 correct += 1;
             }
-            cost += CrossEntropy::cost(&out, label);
+            cost += network.cost.cost(&out, label);
         }
         println!("> Cost: {:.3}\n> Test accuracy: {:.1}", cost / test_input.len() as f32, correct as f32 / test_input.len() as f32 * 100.0);
         println!();
@@ -2,44 +2,52 @@ use std::io::Write;
 use std::time::Instant;
 
 use convoluted::activation::sigmoid::Sigmoid;
-use convoluted::array::Array1D;
-use convoluted::cost::{CostFunction, CrossEntropy};
+use convoluted::cost::CostFunction;
 use convoluted::layer::bias::BiasLayer;
 use convoluted::layer::convolution::Convolution;
 use convoluted::layer::pooling::MaxPooling;
 use convoluted::layer::reshape::{Flatten, Shape};
-use convoluted::layer::{dense::DenseLayer, LayerChain};
-use convoluted::Network;
-use rand::{rng, seq::SliceRandom};
+use convoluted::layer::dense::DenseLayer;
+use convoluted::optimizer::adam::Adam;
+use convoluted::sequential;
+use mnist::ConvolutionNetwork;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+const BATCH_SIZE: usize = 128;
+/// how many parts `learn_batch_parallel` splits a batch into, enough to keep 32 cores busy
+const SHARDS: usize = 32;
 
 fn main() {
-    let mut network = Network::<Array1D<{ 28*28 }>, _, CrossEntropy, Array1D<10>, _>::new(
-        LayerChain::new(Shape::<784, 28, 28>{}, ())
-            .push(Convolution::<5>::random())
-            .push(BiasLayer::<28, 28>::random())
-            .push(Sigmoid::new())
-            .push(MaxPooling::<2, 14, 14>{})
-            .push(Convolution::<3>::random())
-            .push(BiasLayer::<14, 14>::random())
-            .push(Sigmoid::new())
-            .push(Flatten{})
-            .push(DenseLayer::<{ 14*14 }, 64>::random())
-            .push(Sigmoid::new())
-            .push(DenseLayer::<64, 10>::random())
-            .push(Sigmoid::new())
-    );
-    let (input, labels) = mnist::get_mnist_train();
+    // seeded, so runs can be reproduced
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut network = ConvolutionNetwork::new(sequential![
+        Shape{},
+        Convolution::random_with_rng(&mut rng),
+        BiasLayer::random_with_rng(&mut rng),
+        Sigmoid::new(),
+        MaxPooling{},
+        Convolution::random_with_rng(&mut rng),
+        BiasLayer::random_with_rng(&mut rng),
+        Sigmoid::new(),
+        Flatten{},
+        DenseLayer::random_with_rng(&mut rng),
+        Sigmoid::new(),
+        DenseLayer::random_with_rng(&mut rng),
+        Sigmoid::new(),
+    ]);
+    let (input, labels) = mnist::get_mnist_train(&mut rng);
     let mut data: Vec<_> = input.into_iter().zip(labels).collect();
     let (test_input, test_labels) = mnist::get_mnist_test();
-    let mut rng = rng();
+    let mut optimizer = Adam::new(0.001);
     for x in 0..10 {
         println!("Epoch {}/10", x+1);
         let start_time = Instant::now();
         data.shuffle(&mut rng);
-        for (i, chunk) in data.chunks(10).enumerate() {
-            network.learn_batch(chunk.to_owned(), 1.0);
-            if i % 89 == 0 || i == 5999 {
-                print!("\r{:04}/6000 | [{}>{}] {:.1}%", i+1, "=".repeat(i/300), " ".repeat(19 - i/300), (i+1) as f32 / 60.0);
+        let batches = data.len().div_ceil(BATCH_SIZE);
+        for (i, chunk) in data.chunks(BATCH_SIZE).enumerate() {
+            network.learn_batch_parallel(chunk.to_owned(), &mut optimizer, SHARDS);
+            if i % 10 == 0 || i == batches - 1 {
+                print!("\r{:03}/{batches} | [{}>{}] {:.1}%", i+1, "=".repeat(20 * i / batches), " ".repeat(19 - 20 * i / batches), (i+1) as f32 / batches as f32 * 100.0);
                 std::io::stdout().flush().unwrap();
             }
         }
@@ -48,11 +56,11 @@ fn main() {
         let mut cost = 0.0;
         let mut correct = 0;
         for (input, label) in test_input.iter().zip(&test_labels) {
-            let out = network.forward(input.clone()).0;
+            let out = network.infer(input.clone());
             if out.iter().enumerate().max_by(|(_, x), (_, y)| {x.partial_cmp(y).unwrap()}).unwrap().0 == *label {
                 correct += 1;
             }
-            cost += CrossEntropy::cost(&out, label);
+            cost += network.cost.cost(&out, label);
         }
         println!("> Cost: {:.3}\n> Test accuracy: {:.1}", cost / test_input.len() as f32, correct as f32 / test_input.len() as f32 * 100.0);
         println!();
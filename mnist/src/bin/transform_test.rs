@@ -1,7 +1,7 @@
 use raylib::prelude::*;
 
 fn main() {
-    let train = mnist::get_mnist_train().0;
+    let train = mnist::get_mnist_train(&mut rand::rng()).0;
 
     let (mut rl, rt) = init()
     .size(28*20, 28*20)
@@ -1,23 +1,55 @@
 use std::fs;
 
+use convoluted::activation::sigmoid::Sigmoid;
 use convoluted::array::Array1D;
+use convoluted::cost::CrossEntropy;
+use convoluted::layer::{bias::BiasLayer, convolution::Convolution, dense::DenseLayer, dropout::Dropout, pooling::MaxPooling, reshape::{Flatten, Shape}};
+use convoluted::network;
 use rand::Rng;
 
+network! {
+    /// saved to `network_dense.bin` by the `train` binary, outputs logits that `Softmax` turns into probabilities
+    pub type DenseNetwork = Array1D<{ 28*28 }> => [
+        DenseLayer<{ 28*28 }, 100>,
+        Sigmoid,
+        Dropout<20>,
+        DenseLayer<100, 10>,
+    ] => CrossEntropy, usize;
+}
+network! {
+    /// saved to `network.bin` by the `train_convolution` binary
+    pub type ConvolutionNetwork = Array1D<{ 28*28 }> => [
+        Shape<784, 28, 28>,
+        Convolution<5>,
+        BiasLayer<28, 28>,
+        Sigmoid,
+        MaxPooling<2, 14, 14>,
+        Convolution<3>,
+        BiasLayer<14, 14>,
+        Sigmoid,
+        Flatten<{ 14*14 }, 14, 14>,
+        DenseLayer<{ 14*14 }, 64>,
+        Sigmoid,
+        DenseLayer<64, 10>,
+        Sigmoid,
+    ] => CrossEntropy, usize;
+}
+
 // Get mnist csv's from:
 //     https://github.com/phoebetronic/mnist/
 
-pub fn get_mnist_train() -> (Vec<Array1D<{ 28*28 }>>, Vec<usize>) {
+/// augmented with random rotations, scales and translations drawn from `rng`
+pub fn get_mnist_train(rng: &mut (impl Rng + ?Sized)) -> (Vec<Array1D<{ 28*28 }>>, Vec<usize>) {
     let mut data = Vec::with_capacity(60_000);
     let mut labels = Vec::with_capacity(60_000);
     let mut n = 0;
-    let mut src = rand::rng();
     for x in fs::read_to_string("src/mnist_train.csv").unwrap().trim_end().split('\n') {
         labels.push(x[0..1].parse::<usize>().unwrap());
         data.push(Array1D::new());
         for (i, pixel) in x.split(',').skip(1).enumerate() {
             data.last_mut().unwrap()[i] = (pixel.parse::<u8>().unwrap() as f32)/255.0;
         }
-        *data.last_mut().unwrap() = transform(data.last().unwrap(), src.random::<f32>()/2.0-0.25, (src.random::<f32>()-0.5)*0.3+1.05, (src.random::<f32>()*6.0-3.0, src.random::<f32>()*6.0-3.0));
+        *data.last_mut().unwrap() = transform(data.last().unwrap(), rng.random::<f32>()/2.0-0.25, (rng.random::<f32>()-0.5)*0.3+1.05, (rng.random::<f32>()*6.0-3.0, rng.random::<f32>()*6.0-3.0));
         n+=1;
         if n % 20000==0 {
             println!("{}% loading mnist", n/600)
@@ -0,0 +1,86 @@
+use super::Activation;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// `x` for positive inputs, `alpha * (e^x - 1)` for negative ones
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Elu {
+    pub alpha: f32,
+}
+
+impl Default for Elu {
+    fn default() -> Self {
+        Self { alpha: 1.0 }
+    }
+}
+
+impl Elu {
+    /// with an alpha of 1
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_alpha(alpha: f32) -> Self {
+        Self { alpha }
+    }
+}
+
+impl Activation for Elu {
+    type Gradients = ();
+
+    #[inline]
+    fn activate(&self, x: f32) -> f32 {
+        if x > 0.0 {
+            x
+        } else {
+            self.alpha * x.exp_m1()
+        }
+    }
+    #[inline]
+    fn derivate(&self, x: f32) -> f32 {
+        if x > 0.0 {
+            1.0
+        } else {
+            self.alpha * x.exp()
+        }
+    }
+}
+
+/// Scaled ELU, `scale * Elu { alpha }`, self-normalizing with the defaults
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Selu {
+    pub alpha: f32,
+    pub scale: f32,
+}
+
+impl Default for Selu {
+    fn default() -> Self {
+        Self { alpha: 1.673_263_2, scale: 1.050_701 }
+    }
+}
+
+impl Selu {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_alpha_and_scale(alpha: f32, scale: f32) -> Self {
+        Self { alpha, scale }
+    }
+}
+
+impl Activation for Selu {
+    type Gradients = ();
+
+    #[inline]
+    fn activate(&self, x: f32) -> f32 {
+        self.scale * Elu::with_alpha(self.alpha).activate(x)
+    }
+    #[inline]
+    fn derivate(&self, x: f32) -> f32 {
+        self.scale * Elu::with_alpha(self.alpha).derivate(x)
+    }
+}
@@ -0,0 +1,39 @@
+use super::Activation;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Gaussian error linear unit, with the tanh approximation
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Gelu;
+
+impl Gelu {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Activation for Gelu {
+    type Gradients = ();
+
+    #[inline]
+    fn activate(&self, x: f32) -> f32 {
+        0.5 * x * (1.0 + inner(x).tanh())
+    }
+    #[inline]
+    fn derivate(&self, x: f32) -> f32 {
+        let tanh = inner(x).tanh();
+        let inner_derivative = SQRT_2_OVER_PI * (1.0 + 3.0 * CUBIC * x * x);
+        0.5 * (1.0 + tanh) + 0.5 * x * (1.0 - tanh * tanh) * inner_derivative
+    }
+}
+
+const SQRT_2_OVER_PI: f32 = 0.797_884_6;
+const CUBIC: f32 = 0.044_715;
+
+#[inline]
+fn inner(x: f32) -> f32 {
+    SQRT_2_OVER_PI * (x + CUBIC * x * x * x)
+}
@@ -0,0 +1,39 @@
+use super::Activation;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Piecewise linear `Sigmoid`, `clamp(x / 6 + 0.5, 0, 1)`
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct HardSigmoid;
+
+impl HardSigmoid {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Activation for HardSigmoid {
+    type Gradients = ();
+
+    const FROM_OUTPUT: bool = true;
+
+    #[inline]
+    fn activate(&self, x: f32) -> f32 {
+        (x / 6.0 + 0.5).clamp(0.0, 1.0)
+    }
+    #[inline]
+    fn derivate(&self, x: f32) -> f32 {
+        self.derivate_from_output(self.activate(x))
+    }
+    #[inline]
+    fn derivate_from_output(&self, y: f32) -> f32 {
+        if y > 0.0 && y < 1.0 {
+            1.0 / 6.0
+        } else {
+            0.0
+        }
+    }
+}
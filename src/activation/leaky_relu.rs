@@ -3,6 +3,7 @@ use super::Activation;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+/// `x` for positive inputs, `0.01 * x` for negative ones
 #[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
@@ -12,11 +13,17 @@ impl LeakyRelu {
     pub fn new() -> Self {
         Self
     }
+    /// a `CustomLeakyRelu` with another slope than 0.01
+    pub fn with_slope(slope: f32) -> CustomLeakyRelu {
+        CustomLeakyRelu { slope }
+    }
 }
 
 impl Activation for LeakyRelu {
+    type Gradients = ();
+
     #[inline]
-    fn activate(x: f32) -> f32 {
+    fn activate(&self, x: f32) -> f32 {
         if x < 0.0 {
             x * 0.01
         } else {
@@ -24,7 +31,48 @@ impl Activation for LeakyRelu {
         }
     }
     #[inline]
-    fn derivate(x: f32) -> f32 {
+    fn derivate(&self, x: f32) -> f32 {
         (x >= 0.0) as u32 as f32 * 0.99 + 0.01
     }
-}
\ No newline at end of file
+}
+
+/// `x` for positive inputs, `slope * x` for negative ones
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CustomLeakyRelu {
+    pub slope: f32,
+}
+
+impl Default for CustomLeakyRelu {
+    fn default() -> Self {
+        Self { slope: 0.01 }
+    }
+}
+
+impl CustomLeakyRelu {
+    pub fn new(slope: f32) -> Self {
+        Self { slope }
+    }
+}
+
+impl Activation for CustomLeakyRelu {
+    type Gradients = ();
+
+    #[inline]
+    fn activate(&self, x: f32) -> f32 {
+        if x < 0.0 {
+            x * self.slope
+        } else {
+            x
+        }
+    }
+    #[inline]
+    fn derivate(&self, x: f32) -> f32 {
+        if x < 0.0 {
+            self.slope
+        } else {
+            1.0
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use super::{sigmoid::Sigmoid, softplus::Softplus, Activation};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// `x * tanh(softplus(x))`
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Mish;
+
+impl Mish {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Activation for Mish {
+    type Gradients = ();
+
+    #[inline]
+    fn activate(&self, x: f32) -> f32 {
+        x * Softplus.activate(x).tanh()
+    }
+    #[inline]
+    fn derivate(&self, x: f32) -> f32 {
+        let tanh = Softplus.activate(x).tanh();
+        tanh + x * Sigmoid.activate(x) * (1.0 - tanh * tanh)
+    }
+}
@@ -1,61 +1,277 @@
-use crate::{array::{Array1D, Array2D}, layer::Layer};
+use crate::{array::{Array1D, Array2D, Array3D, Tensor}, layer::{chain::Leaf, ForwardContext, Layer, Parameter, ParameterMut, ParameterPath}};
 
 pub mod relu;
 pub mod leaky_relu;
+pub mod elu;
+pub mod prelu;
 pub mod sigmoid;
+pub mod tanh;
+pub mod gelu;
+pub mod silu;
+pub mod mish;
+pub mod softplus;
+pub mod softsign;
+pub mod hard_sigmoid;
+pub mod hard_tanh;
+pub mod softmax;
 
+/// An element-wise function, every `Activation` is a `Layer` for 1D, 2D and 3D arrays
 pub trait Activation {
-    fn activate(x: f32) -> f32;
-    fn derivate(x: f32) -> f32;
+    /// Gradients of the trainable parameters, `()` if there are none
+    type Gradients: Tensor;
+
+    /// Whether the layer derivates with `derivate_from_output`, so it doesn't need a copy of its input.
+    /// Only for activations without parameters, that is checked at compile time.
+    const FROM_OUTPUT: bool = false;
+
+    fn activate(&self, x: f32) -> f32;
+    fn derivate(&self, x: f32) -> f32;
+    /// The derivative at the input that `activate` mapped to `y`, only used if `FROM_OUTPUT`
+    fn derivate_from_output(&self, _y: f32) -> f32 {
+        unimplemented!("activations with `FROM_OUTPUT` implement `derivate_from_output`")
+    }
+
+    /// Trainable parameters, laid out like `Gradients`
+    fn parameters(&self) -> &[f32] {
+        &[]
+    }
+    fn parameters_mut(&mut self) -> &mut [f32] {
+        &mut []
+    }
+    /// Adds `gradient` times the derivative of `activate(x)` by every parameter onto `gradients`
+    fn parameter_gradients(&self, _x: f32, _gradient: f32, _gradients: &mut Self::Gradients) {}
+}
+
+impl<T: Activation> Leaf for T {}
+
+fn activate<T: Activation>(activation: &T, values: &mut [f32]) {
+    for x in values {
+        *x = activation.activate(*x);
+    }
+}
+
+/// Activates `input` and returns it with the forward data: the input, or the output if `T::FROM_OUTPUT`.
+/// That output isn't copied if the next layer keeps it anyways.
+fn forward<T: Activation, A: Clone>(activation: &T, mut input: A, context: ForwardContext, values: impl Fn(&mut A) -> &mut [f32]) -> (A, Option<A>) {
+    const { assert!(!T::FROM_OUTPUT || size_of::<T::Gradients>() == 0, "activations with parameters can't derivate from their output") };
+    if T::FROM_OUTPUT {
+        activate(activation, values(&mut input));
+        let forward_data = (!context.output_kept).then(|| input.clone());
+        (input, forward_data)
+    } else {
+        let forward_data = input.clone();
+        activate(activation, values(&mut input));
+        (input, Some(forward_data))
+    }
+}
+
+fn derivate<T: Activation>(activation: &T, forward: &mut [f32], forward_data: &[f32]) -> T::Gradients {
+    let mut gradients = T::Gradients::zeroed();
+    for (forward, x) in forward.iter_mut().zip(forward_data) {
+        if !T::FROM_OUTPUT {
+            activation.parameter_gradients(*x, *forward, &mut gradients);
+        }
+        *forward *= if T::FROM_OUTPUT { activation.derivate_from_output(*x) } else { activation.derivate(*x) };
+    }
+    gradients
+}
+
+fn apply_gradients<T: Activation>(activation: &mut T, gradients: T::Gradients, multiplier: f32) {
+    for (parameter, gradient) in activation.parameters_mut().iter_mut().zip(gradients.slices().flatten()) {
+        *parameter += *gradient * multiplier;
+    }
+}
+
+fn visit_parameters<T: Activation>(activation: &T, visitor: &mut dyn FnMut(Parameter)) {
+    let values = activation.parameters();
+    if !values.is_empty() {
+        visitor(Parameter { path: ParameterPath::field("parameters"), decays: false, shape: &[values.len()], values });
+    }
+}
+
+fn visit_parameters_mut<T: Activation>(activation: &mut T, visitor: &mut dyn FnMut(ParameterMut)) {
+    let values = activation.parameters_mut();
+    if !values.is_empty() {
+        visitor(ParameterMut { path: ParameterPath::field("parameters"), decays: false, shape: &[values.len()], values });
+    }
 }
 
 impl<T: Activation, const N: usize> Layer<Array1D<N>> for T {
     type Output = Array1D<N>;
 
-    type ForwardData = Array1D<N>;
+    /// the input, or the output if `T::FROM_OUTPUT` and the next layer doesn't keep it
+    type ForwardData = Option<Array1D<N>>;
 
-    type Gradients = ();
+    type Gradients = T::Gradients;
 
-    fn forward(&self, mut input: Array1D<N>) -> (Self::Output, Self::ForwardData) {
-        let forward_data = input.clone();
-        for x in input.iter_mut() {
-            *x = Self::activate(*x);
-        }
-        (input, forward_data)
+    fn forward(&self, input: Array1D<N>) -> (Self::Output, Self::ForwardData) {
+        self.forward_in(input, ForwardContext::default())
     }
 
-    fn backward(&self, mut forward: Self::Output, forward_data: Self::ForwardData) -> (Array1D<N>, Self::Gradients) {
-        for (forward, input) in forward.iter_mut().zip(forward_data.iter()) {
-            *forward *= Self::derivate(*input);
-        }
-        (forward, ())
+    fn forward_in(&self, input: Array1D<N>, context: ForwardContext) -> (Self::Output, Self::ForwardData) {
+        forward(self, input, context, |input| input.as_mut_slice())
+    }
+
+    fn infer(&self, mut input: Array1D<N>) -> Self::Output {
+        activate(self, input.as_mut_slice());
+        input
+    }
+
+    fn backward(&self, forward: Self::Output, forward_data: Self::ForwardData) -> (Array1D<N>, Self::Gradients) {
+        let (input, gradients, _) = self.backward_with(forward, forward_data, None);
+        (input, gradients)
+    }
+
+    fn backward_with(&self, mut forward: Self::Output, forward_data: Self::ForwardData, output: Option<Self::Output>) -> (Array1D<N>, Self::Gradients, Option<Array1D<N>>) {
+        let forward_data = forward_data.or(output).expect("the output of `forward_in` is passed back if the next layer kept it");
+        let gradients = derivate(self, forward.as_mut_slice(), forward_data.as_slice());
+        (forward, gradients, None)
+    }
+
+    fn apply_gradients(&mut self, gradients: Self::Gradients, multiplier: f32) {
+        apply_gradients(self, gradients, multiplier);
+    }
+
+    fn visit_parameters(&self, visitor: &mut dyn FnMut(Parameter)) {
+        visit_parameters(self, visitor);
+    }
+
+    fn visit_parameters_mut(&mut self, visitor: &mut dyn FnMut(ParameterMut)) {
+        visit_parameters_mut(self, visitor);
     }
-    
-    fn apply_gradients(&mut self, _gradients: Self::Gradients, _multiplier: f32) {}
 }
 impl<T: Activation, const X: usize, const Y: usize> Layer<Array2D<X, Y>> for T {
     type Output = Array2D<X, Y>;
-    type ForwardData = Array2D<X, Y>;
-    type Gradients = ();
+    type ForwardData = Option<Array2D<X, Y>>;
+    type Gradients = T::Gradients;
 
-    fn forward(&self, mut input: Array2D<X, Y>) -> (Self::Output, Self::ForwardData) {
-        let forward_data = input.clone();
-        for x in input.iter_mut() {
-            for y in x {
-                *y = Self::activate(*y);
-            }
-        }
-        (input, forward_data)
+    fn forward(&self, input: Array2D<X, Y>) -> (Self::Output, Self::ForwardData) {
+        self.forward_in(input, ForwardContext::default())
     }
 
-    fn backward(&self, mut forward: Self::Output, forward_data: Self::ForwardData) -> (Array2D<X, Y>, Self::Gradients) {
-        for (forward, input) in forward.iter_mut().zip(forward_data.iter()) {
-            for (forward, input) in forward.iter_mut().zip(input.iter()) {
-                *forward *= Self::derivate(*input);
-            }
-        }
-        (forward, ())
+    fn forward_in(&self, input: Array2D<X, Y>, context: ForwardContext) -> (Self::Output, Self::ForwardData) {
+        forward(self, input, context, |input| input.as_flattened_mut())
+    }
+
+    fn infer(&self, mut input: Array2D<X, Y>) -> Self::Output {
+        activate(self, input.as_flattened_mut());
+        input
+    }
+
+    fn backward(&self, forward: Self::Output, forward_data: Self::ForwardData) -> (Array2D<X, Y>, Self::Gradients) {
+        let (input, gradients, _) = self.backward_with(forward, forward_data, None);
+        (input, gradients)
+    }
+
+    fn backward_with(&self, mut forward: Self::Output, forward_data: Self::ForwardData, output: Option<Self::Output>) -> (Array2D<X, Y>, Self::Gradients, Option<Array2D<X, Y>>) {
+        let forward_data = forward_data.or(output).expect("the output of `forward_in` is passed back if the next layer kept it");
+        let gradients = derivate(self, forward.as_flattened_mut(), forward_data.as_flattened());
+        (forward, gradients, None)
+    }
+
+    fn apply_gradients(&mut self, gradients: Self::Gradients, multiplier: f32) {
+        apply_gradients(self, gradients, multiplier);
+    }
+
+    fn visit_parameters(&self, visitor: &mut dyn FnMut(Parameter)) {
+        visit_parameters(self, visitor);
     }
 
-    fn apply_gradients(&mut self, _gradients: Self::Gradients, _multiplier: f32) {}
-}
\ No newline at end of file
+    fn visit_parameters_mut(&mut self, visitor: &mut dyn FnMut(ParameterMut)) {
+        visit_parameters_mut(self, visitor);
+    }
+}
+impl<T: Activation, const C: usize, const X: usize, const Y: usize> Layer<Array3D<C, X, Y>> for T {
+    type Output = Array3D<C, X, Y>;
+    type ForwardData = Option<Array3D<C, X, Y>>;
+    type Gradients = T::Gradients;
+
+    fn forward(&self, input: Array3D<C, X, Y>) -> (Self::Output, Self::ForwardData) {
+        self.forward_in(input, ForwardContext::default())
+    }
+
+    fn forward_in(&self, input: Array3D<C, X, Y>, context: ForwardContext) -> (Self::Output, Self::ForwardData) {
+        forward(self, input, context, |input| input.as_flattened_mut().as_flattened_mut())
+    }
+
+    fn infer(&self, mut input: Array3D<C, X, Y>) -> Self::Output {
+        activate(self, input.as_flattened_mut().as_flattened_mut());
+        input
+    }
+
+    fn backward(&self, forward: Self::Output, forward_data: Self::ForwardData) -> (Array3D<C, X, Y>, Self::Gradients) {
+        let (input, gradients, _) = self.backward_with(forward, forward_data, None);
+        (input, gradients)
+    }
+
+    fn backward_with(&self, mut forward: Self::Output, forward_data: Self::ForwardData, output: Option<Self::Output>) -> (Array3D<C, X, Y>, Self::Gradients, Option<Array3D<C, X, Y>>) {
+        let forward_data = forward_data.or(output).expect("the output of `forward_in` is passed back if the next layer kept it");
+        let gradients = derivate(self, forward.as_flattened_mut().as_flattened_mut(), forward_data.as_flattened().as_flattened());
+        (forward, gradients, None)
+    }
+
+    fn apply_gradients(&mut self, gradients: Self::Gradients, multiplier: f32) {
+        apply_gradients(self, gradients, multiplier);
+    }
+
+    fn visit_parameters(&self, visitor: &mut dyn FnMut(Parameter)) {
+        visit_parameters(self, visitor);
+    }
+
+    fn visit_parameters_mut(&mut self, visitor: &mut dyn FnMut(ParameterMut)) {
+        visit_parameters_mut(self, visitor);
+    }
+}
+
+#[test]
+fn parameterized_activations_pass_gradcheck() {
+    use crate::gradcheck::check_linear;
+    use self::{elu::{Elu, Selu}, leaky_relu::LeakyRelu, prelu::PRelu};
+
+    // away from 0, where most of these have a kink
+    let input = Array1D::<6>::from([-2.0, -0.7, -0.1, 0.3, 0.9, 1.8].as_slice());
+    let checks = [
+        check_linear(&LeakyRelu::with_slope(0.2), &input, 1e-3),
+        check_linear(&Elu::with_alpha(0.5), &input, 1e-3),
+        check_linear(&Selu::new(), &input, 1e-3),
+        check_linear(&PRelu::with_slope(0.3), &input, 1e-3),
+        check_linear(&tanh::Tanh, &input, 1e-3),
+        check_linear(&LeakyRelu, &input, 1e-3),
+    ];
+    for (i, check) in checks.iter().enumerate() {
+        assert!(check.max_error() < 1e-2, "activation {i}: {check:?}");
+    }
+    assert_eq!(checks[3].parameters.len(), 1);
+
+    // the slope isn't a weight, so weight decay leaves it alone
+    let mut prelu = PRelu::with_slope(0.3);
+    Layer::<Array1D<6>>::decay_weights(&mut prelu, 0.5);
+    assert_eq!(prelu.slope, 0.3);
+}
+
+#[test]
+fn derivatives_match_numeric() {
+    use self::{gelu::Gelu, hard_sigmoid::HardSigmoid, hard_tanh::HardTanh, mish::Mish, sigmoid::Sigmoid, silu::Silu, softplus::Softplus, softsign::Softsign, tanh::Tanh};
+
+    // none of these lands on a kink of the hard activations
+    let input = Array1D::<8>::from([-4.5, -2.2, -0.6, -0.05, 0.4, 1.3, 2.9, 4.5].as_slice());
+    fn check<A: Activation + Clone>(name: &str, activation: A, input: &Array1D<8>)
+    where
+        A::Gradients: Clone, {
+        let check = crate::gradcheck::check_linear(&activation, input, 1e-2);
+        assert!(check.max_error() < 1e-3, "{name}: {check:?}");
+        if A::FROM_OUTPUT {
+            assert!(input.iter().all(|x| (activation.derivate(*x) - activation.derivate_from_output(activation.activate(*x))).abs() < 1e-6), "{name}");
+        }
+    }
+    check("tanh", Tanh, &input);
+    check("gelu", Gelu, &input);
+    check("silu", Silu, &input);
+    check("mish", Mish, &input);
+    check("softplus", Softplus, &input);
+    check("softsign", Softsign, &input);
+    check("hard sigmoid", HardSigmoid, &input);
+    check("hard tanh", HardTanh, &input);
+    check("sigmoid", Sigmoid, &input);
+    assert!((Softplus.activate(100.0) - 100.0).abs() < 1e-5);
+}
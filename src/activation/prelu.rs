@@ -0,0 +1,64 @@
+use crate::array::Array1D;
+
+use super::Activation;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A `LeakyRelu` that learns its slope, shared by every element of the input
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PRelu {
+    pub slope: f32,
+}
+
+impl Default for PRelu {
+    fn default() -> Self {
+        Self { slope: 0.25 }
+    }
+}
+
+impl PRelu {
+    /// with a starting slope of 0.25
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_slope(slope: f32) -> Self {
+        Self { slope }
+    }
+}
+
+impl Activation for PRelu {
+    /// the slope
+    type Gradients = Array1D<1>;
+
+    #[inline]
+    fn activate(&self, x: f32) -> f32 {
+        if x < 0.0 {
+            x * self.slope
+        } else {
+            x
+        }
+    }
+    #[inline]
+    fn derivate(&self, x: f32) -> f32 {
+        if x < 0.0 {
+            self.slope
+        } else {
+            1.0
+        }
+    }
+
+    fn parameters(&self) -> &[f32] {
+        std::slice::from_ref(&self.slope)
+    }
+    fn parameters_mut(&mut self) -> &mut [f32] {
+        std::slice::from_mut(&mut self.slope)
+    }
+    fn parameter_gradients(&self, x: f32, gradient: f32, gradients: &mut Self::Gradients) {
+        if x < 0.0 {
+            gradients[0] += gradient * x;
+        }
+    }
+}
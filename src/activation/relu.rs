@@ -15,12 +15,14 @@ impl Relu {
 }
 
 impl Activation for Relu {
+    type Gradients = ();
+
     #[inline]
-    fn activate(x: f32) -> f32 {
+    fn activate(&self, x: f32) -> f32 {
         x.max(0.0)
     }
     #[inline]
-    fn derivate(x: f32) -> f32 {
+    fn derivate(&self, x: f32) -> f32 {
         (x >= 0.0) as u32 as f32
     }
 }
\ No newline at end of file
@@ -15,13 +15,20 @@ impl Sigmoid {
 }
 
 impl Activation for Sigmoid {
+    type Gradients = ();
+
+    const FROM_OUTPUT: bool = true;
+
     #[inline]
-    fn activate(x: f32) -> f32 {
+    fn activate(&self, x: f32) -> f32 {
         1.0 / (1.0 + (-x).exp())
     }
     #[inline]
-    fn derivate(x: f32) -> f32 {
-        let activated = Self::activate(x);
-        activated * (1.0 - activated)
+    fn derivate(&self, x: f32) -> f32 {
+        self.derivate_from_output(self.activate(x))
+    }
+    #[inline]
+    fn derivate_from_output(&self, y: f32) -> f32 {
+        y * (1.0 - y)
     }
 }
\ No newline at end of file
@@ -0,0 +1,32 @@
+use super::{sigmoid::Sigmoid, Activation};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// `x * sigmoid(x)`, also known as Swish
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Silu;
+
+pub type Swish = Silu;
+
+impl Silu {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Activation for Silu {
+    type Gradients = ();
+
+    #[inline]
+    fn activate(&self, x: f32) -> f32 {
+        x * Sigmoid.activate(x)
+    }
+    #[inline]
+    fn derivate(&self, x: f32) -> f32 {
+        let sigmoid = Sigmoid.activate(x);
+        sigmoid * (1.0 + x * (1.0 - sigmoid))
+    }
+}
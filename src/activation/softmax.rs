@@ -0,0 +1,134 @@
+use crate::{array::Array1D, cost::{log_softmax, log_sum_exp, softmax}, layer::{chain::Leaf, Layer, Parameter, ParameterMut}};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Turns logits into probabilities, `CrossEntropy` already applies this itself
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Softmax;
+
+impl Leaf for Softmax {}
+
+impl Softmax {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<const N: usize> Layer<Array1D<N>> for Softmax {
+    type Output = Array1D<N>;
+
+    /// the probabilities
+    type ForwardData = Array1D<N>;
+
+    type Gradients = ();
+
+    fn forward(&self, input: Array1D<N>) -> (Self::Output, Self::ForwardData) {
+        let output = softmax(&input);
+        (output.clone(), output)
+    }
+
+    fn infer(&self, mut input: Array1D<N>) -> Self::Output {
+        let total = log_sum_exp(&input);
+        for x in input.iter_mut() {
+            *x = (*x - total).exp();
+        }
+        input
+    }
+
+    fn backward(&self, mut forward: Self::Output, forward_data: Self::ForwardData) -> (Array1D<N>, Self::Gradients) {
+        let dot = forward.iter().zip(forward_data.iter()).map(|(g, y)| g * y).sum::<f32>();
+        for (g, y) in forward.iter_mut().zip(forward_data.iter()) {
+            *g = y * (*g - dot);
+        }
+        (forward, ())
+    }
+
+    fn apply_gradients(&mut self, _gradients: Self::Gradients, _multiplier: f32) {}
+
+    fn visit_parameters(&self, _visitor: &mut dyn FnMut(Parameter)) {}
+
+    fn visit_parameters_mut(&mut self, _visitor: &mut dyn FnMut(ParameterMut)) {}
+}
+
+/// Turns logits into log-probabilities, pair it with `Nll`
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LogSoftmax;
+
+impl Leaf for LogSoftmax {}
+
+impl LogSoftmax {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<const N: usize> Layer<Array1D<N>> for LogSoftmax {
+    type Output = Array1D<N>;
+
+    /// the probabilities
+    type ForwardData = Array1D<N>;
+
+    type Gradients = ();
+
+    fn forward(&self, input: Array1D<N>) -> (Self::Output, Self::ForwardData) {
+        let output = log_softmax(&input);
+        let mut probabilities = output.clone();
+        for x in probabilities.iter_mut() {
+            *x = x.exp();
+        }
+        (output, probabilities)
+    }
+
+    fn infer(&self, mut input: Array1D<N>) -> Self::Output {
+        let total = log_sum_exp(&input);
+        for x in input.iter_mut() {
+            *x -= total;
+        }
+        input
+    }
+
+    fn backward(&self, mut forward: Self::Output, forward_data: Self::ForwardData) -> (Array1D<N>, Self::Gradients) {
+        let sum = forward.iter().sum::<f32>();
+        for (g, p) in forward.iter_mut().zip(forward_data.iter()) {
+            *g -= p * sum;
+        }
+        (forward, ())
+    }
+
+    fn apply_gradients(&mut self, _gradients: Self::Gradients, _multiplier: f32) {}
+
+    fn visit_parameters(&self, _visitor: &mut dyn FnMut(Parameter)) {}
+
+    fn visit_parameters_mut(&mut self, _visitor: &mut dyn FnMut(ParameterMut)) {}
+}
+
+#[test]
+fn softmax_backward_matches_numeric() {
+    let input = Array1D::<4>::from([0.3, -1.2, 2.0, 0.5].as_slice());
+    let weights = [0.7, -0.4, 1.1, 0.2];
+    let loss = |layer: &dyn Fn(Array1D<4>) -> Array1D<4>, input: Array1D<4>| {
+        layer(input).iter().zip(weights).map(|(a, b)| a * b).sum::<f32>()
+    };
+    let softmax_layer = |x| Softmax.forward(x).0;
+    let log_softmax_layer = |x| LogSoftmax.forward(x).0;
+
+    let (_, forward_data) = Softmax.forward(input.clone());
+    let (softmax_gradients, ()) = Softmax.backward(Array1D::from(weights.as_slice()), forward_data);
+    let (_, forward_data) = LogSoftmax.forward(input.clone());
+    let (log_softmax_gradients, ()) = LogSoftmax.backward(Array1D::from(weights.as_slice()), forward_data);
+    for (layer, gradients) in [(&softmax_layer as &dyn Fn(_) -> _, softmax_gradients), (&log_softmax_layer, log_softmax_gradients)] {
+        for i in 0..4 {
+            let mut above = input.clone();
+            above[i] += 1e-2;
+            let mut below = input.clone();
+            below[i] -= 1e-2;
+            let numeric = (loss(layer, above) - loss(layer, below)) / 2e-2;
+            assert!((numeric - gradients[i]).abs() < 1e-3, "{numeric} {}", gradients[i]);
+        }
+    }
+}
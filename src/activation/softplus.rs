@@ -0,0 +1,30 @@
+use super::{sigmoid::Sigmoid, Activation};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// `ln(1 + e^x)`, a smooth `Relu`
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Softplus;
+
+impl Softplus {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Activation for Softplus {
+    type Gradients = ();
+
+    #[inline]
+    fn activate(&self, x: f32) -> f32 {
+        // doesn't overflow for large inputs
+        x.max(0.0) + (-x.abs()).exp().ln_1p()
+    }
+    #[inline]
+    fn derivate(&self, x: f32) -> f32 {
+        Sigmoid.activate(x)
+    }
+}
@@ -0,0 +1,29 @@
+use super::Activation;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// `x / (1 + |x|)`
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Softsign;
+
+impl Softsign {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Activation for Softsign {
+    type Gradients = ();
+
+    #[inline]
+    fn activate(&self, x: f32) -> f32 {
+        x / (1.0 + x.abs())
+    }
+    #[inline]
+    fn derivate(&self, x: f32) -> f32 {
+        (1.0 + x.abs()).powi(-2)
+    }
+}
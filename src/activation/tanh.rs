@@ -0,0 +1,35 @@
+use super::Activation;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Hyperbolic tangent
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Tanh;
+
+impl Tanh {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Activation for Tanh {
+    type Gradients = ();
+
+    const FROM_OUTPUT: bool = true;
+
+    #[inline]
+    fn activate(&self, x: f32) -> f32 {
+        x.tanh()
+    }
+    #[inline]
+    fn derivate(&self, x: f32) -> f32 {
+        self.derivate_from_output(self.activate(x))
+    }
+    #[inline]
+    fn derivate_from_output(&self, y: f32) -> f32 {
+        1.0 - y * y
+    }
+}
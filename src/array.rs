@@ -243,6 +243,188 @@ impl<'de, const X: usize, const Y: usize> Deserialize<'de> for Array2D<X, Y>  {
     }
 }
 
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[derive(Clone, Debug)]
+pub struct Array3D<const C: usize, const X: usize, const Y: usize> {
+    pub array: Box<[[[f32; X]; Y]; C]>
+}
+impl<const C: usize, const X: usize, const Y: usize> Default for Array3D<C, X, Y> {
+    fn default() -> Self {
+        let mut array: Box<std::mem::MaybeUninit<[[[f32; X]; Y]; C]>> = Box::new_uninit();
+        for offset in 0..C * X * Y {
+            unsafe { std::ptr::write(array.as_mut_ptr().cast::<f32>().add(offset), 0.0) };
+        }
+        let array = unsafe { array.assume_init() };
+        Self { array }
+    }
+}
+impl<const C: usize, const X: usize, const Y: usize> Array3D<C, X, Y> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+impl<const C: usize, const X: usize, const Y: usize> AsRef<[[[f32; X]; Y]; C]> for Array3D<C, X, Y> {
+    fn as_ref(&self) -> &[[[f32; X]; Y]; C] {
+        &self.array
+    }
+}
+impl<const C: usize, const X: usize, const Y: usize> Borrow<[[[f32; X]; Y]; C]> for Array3D<C, X, Y> {
+    fn borrow(&self) -> &[[[f32; X]; Y]; C] {
+        &self.array
+    }
+}
+impl<const C: usize, const X: usize, const Y: usize> Deref for Array3D<C, X, Y> {
+    type Target = [[[f32; X]; Y]; C];
+
+    fn deref(&self) -> &Self::Target {
+        &self.array
+    }
+}
+impl<const C: usize, const X: usize, const Y: usize> DerefMut for Array3D<C, X, Y> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.array
+    }
+}
+impl<const C: usize, const X: usize, const Y: usize> AddAssign<Array3D<C, X, Y>> for Array3D<C, X, Y> {
+    fn add_assign(&mut self, rhs: Array3D<C, X, Y>) {
+        for (x, y) in self.array.as_flattened_mut().as_flattened_mut().iter_mut().zip(rhs.array.as_flattened().as_flattened()) {
+            *x += *y;
+        }
+    }
+}
+impl<const C: usize, const X: usize, const Y: usize> AddAssign<f32> for Array3D<C, X, Y> {
+    fn add_assign(&mut self, rhs: f32) {
+        for element in self.array.as_flattened_mut().as_flattened_mut() {
+            *element += rhs;
+        }
+    }
+}
+impl<const C: usize, const X: usize, const Y: usize> MulAssign<Array3D<C, X, Y>> for Array3D<C, X, Y> {
+    fn mul_assign(&mut self, rhs: Array3D<C, X, Y>) {
+        for (x, y) in self.array.as_flattened_mut().as_flattened_mut().iter_mut().zip(rhs.array.as_flattened().as_flattened()) {
+            *x *= *y;
+        }
+    }
+}
+impl<const C: usize, const X: usize, const Y: usize> MulAssign<f32> for Array3D<C, X, Y> {
+    fn mul_assign(&mut self, rhs: f32) {
+        for element in self.array.as_flattened_mut().as_flattened_mut() {
+            *element *= rhs;
+        }
+    }
+}
+impl<const C: usize, const X: usize, const Y: usize> From<&Vec<Vec<Vec<f32>>>> for Array3D<C, X, Y> {
+    fn from(value: &Vec<Vec<Vec<f32>>>) -> Self {
+        assert_eq!(C, value.len());
+        let mut new = Self::default();
+        for (channel_x, channel_y) in new.iter_mut().zip(value) {
+            assert_eq!(Y, channel_y.len());
+            for (row_x, row_y) in channel_x.iter_mut().zip(channel_y) {
+                assert_eq!(row_y.len(), X);
+                row_x.copy_from_slice(row_y);
+            }
+        }
+        new
+    }
+}
+impl<const C: usize, const X: usize, const Y: usize> From<Box<[[[f32; X]; Y]; C]>> for Array3D<C, X, Y> {
+    fn from(value: Box<[[[f32; X]; Y]; C]>) -> Self {
+        Self { array: value }
+    }
+}
+impl<const C: usize, const X: usize, const Y: usize> From<&Array3D<C, X, Y>> for Vec<Vec<Vec<f32>>> {
+    fn from(value: &Array3D<C, X, Y>) -> Self {
+        value.iter().map(|channel| channel.iter().map(|row| row.to_vec()).collect()).collect()
+    }
+}
+#[cfg(feature = "serde")]
+impl<const C: usize, const X: usize, const Y: usize> Serialize for Array3D<C, X, Y> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+        Vec::<Vec<Vec<f32>>>::from(self).serialize(serializer)
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de, const C: usize, const X: usize, const Y: usize> Deserialize<'de> for Array3D<C, X, Y>  {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de> {
+        Vec::<Vec<Vec<f32>>>::deserialize(deserializer).map(|x| Self::from(&x))
+    }
+}
+
+/// Something made of flat `f32` buffers, like a layer's gradients. Optimizers use this to keep state shaped like the gradients they see.
+pub trait Tensor {
+    fn zeroed() -> Self;
+    fn slices(&self) -> impl Iterator<Item = &[f32]>;
+    fn slices_mut(&mut self) -> impl Iterator<Item = &mut [f32]>;
+}
+impl Tensor for () {
+    fn zeroed() -> Self {}
+    fn slices(&self) -> impl Iterator<Item = &[f32]> {
+        std::iter::empty()
+    }
+    fn slices_mut(&mut self) -> impl Iterator<Item = &mut [f32]> {
+        std::iter::empty()
+    }
+}
+impl<const N: usize> Tensor for Array1D<N> {
+    fn zeroed() -> Self {
+        Self::new()
+    }
+    fn slices(&self) -> impl Iterator<Item = &[f32]> {
+        std::iter::once(self.array.as_slice())
+    }
+    fn slices_mut(&mut self) -> impl Iterator<Item = &mut [f32]> {
+        std::iter::once(self.array.as_mut_slice())
+    }
+}
+impl<const X: usize, const Y: usize> Tensor for Array2D<X, Y> {
+    fn zeroed() -> Self {
+        Self::new()
+    }
+    fn slices(&self) -> impl Iterator<Item = &[f32]> {
+        std::iter::once(self.array.as_flattened())
+    }
+    fn slices_mut(&mut self) -> impl Iterator<Item = &mut [f32]> {
+        std::iter::once(self.array.as_flattened_mut())
+    }
+}
+impl<const C: usize, const X: usize, const Y: usize> Tensor for Array3D<C, X, Y> {
+    fn zeroed() -> Self {
+        Self::new()
+    }
+    fn slices(&self) -> impl Iterator<Item = &[f32]> {
+        std::iter::once(self.array.as_flattened().as_flattened())
+    }
+    fn slices_mut(&mut self) -> impl Iterator<Item = &mut [f32]> {
+        std::iter::once(self.array.as_flattened_mut().as_flattened_mut())
+    }
+}
+impl<T: Tensor, const N: usize> Tensor for [T; N] {
+    fn zeroed() -> Self {
+        std::array::from_fn(|_| T::zeroed())
+    }
+    fn slices(&self) -> impl Iterator<Item = &[f32]> {
+        self.iter().flat_map(|x| x.slices())
+    }
+    fn slices_mut(&mut self) -> impl Iterator<Item = &mut [f32]> {
+        self.iter_mut().flat_map(|x| x.slices_mut())
+    }
+}
+impl<A: Tensor, B: Tensor> Tensor for (A, B) {
+    fn zeroed() -> Self {
+        (A::zeroed(), B::zeroed())
+    }
+    fn slices(&self) -> impl Iterator<Item = &[f32]> {
+        self.0.slices().chain(self.1.slices())
+    }
+    fn slices_mut(&mut self) -> impl Iterator<Item = &mut [f32]> {
+        self.0.slices_mut().chain(self.1.slices_mut())
+    }
+}
+
 #[test]
 fn huge_array_test() {
     let array: Array1D<2000000> = Array1D::new();
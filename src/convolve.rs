@@ -0,0 +1,266 @@
+//! Backends for the 2D cross-correlations behind the convolution layers.
+//!
+//! Every backend computes, for each kernel `r`,
+//! `out[r][oy][ox] += Σ kernel[r][py][px] * input[py + oy - offset][px + ox - offset]`
+//! with zeros outside of the input. `Direct` and `Im2col` add the products in the same order and round identically,
+//! `Fft` only agrees up to rounding.
+
+use std::f64::consts::PI;
+
+use crate::layer::convolution::ConvolutionAlgorithm;
+
+/// A `width x height` plane stored row by row
+#[derive(Clone, Copy)]
+pub(crate) struct Plane<'a> {
+    data: &'a [f32],
+    width: usize,
+    height: usize,
+}
+
+impl<'a> Plane<'a> {
+    pub fn new<const X: usize, const Y: usize>(array: &'a [[f32; X]; Y]) -> Self {
+        Self { data: array.as_flattened(), width: X, height: Y }
+    }
+}
+
+/// Correlates `input` with every plane of `kernels` and adds the `width x height` results onto `outputs`
+pub(crate) fn correlate(algorithm: ConvolutionAlgorithm, input: Plane, kernels: &[Plane], offset: usize, outputs: &mut [&mut [f32]], width: usize, height: usize) {
+    let Some(kernel) = kernels.first() else {
+        return;
+    };
+    let algorithm = match algorithm {
+        ConvolutionAlgorithm::Auto => choose(input, *kernel, kernels.len(), offset, width, height),
+        algorithm => algorithm,
+    };
+    match algorithm {
+        ConvolutionAlgorithm::Direct | ConvolutionAlgorithm::Auto => direct(input, kernels, offset, outputs, width, height),
+        ConvolutionAlgorithm::Im2col => im2col(input, kernels, offset, outputs, width, height),
+        ConvolutionAlgorithm::Fft => fft(input, kernels, offset, outputs, width, height),
+    }
+}
+
+/// im2col unless the FFT's transforms are cheaper than the multiply-adds
+fn choose(input: Plane, kernel: Plane, count: usize, offset: usize, width: usize, height: usize) -> ConvolutionAlgorithm {
+    let (size_x, size_y) = fft_size(input, kernel, offset, width, height);
+    let multiply_adds = count * width * height * kernel.width * kernel.height;
+    // one forward transform per kernel, one for the input and one inverse per kernel
+    let fft = (2 * count + 1) * size_x * size_y * (size_x * size_y).ilog2() as usize * FFT_COST;
+    if fft < multiply_adds {
+        ConvolutionAlgorithm::Fft
+    } else {
+        ConvolutionAlgorithm::Im2col
+    }
+}
+/// roughly how many im2col multiply-adds one point of one transform level costs
+const FFT_COST: usize = 6;
+
+#[inline]
+fn sample(input: Plane, x: usize, y: usize, offset: usize) -> f32 {
+    // wraps to a huge index, and so to 0, when the window hangs over the top or left edge
+    let (x, y) = (x.wrapping_sub(offset), y.wrapping_sub(offset));
+    if x < input.width && y < input.height {
+        input.data[y * input.width + x]
+    } else {
+        0.0
+    }
+}
+
+fn direct(input: Plane, kernels: &[Plane], offset: usize, outputs: &mut [&mut [f32]], width: usize, height: usize) {
+    for (kernel, output) in kernels.iter().zip(outputs.iter_mut()) {
+        for oy in 0..height {
+            for ox in 0..width {
+                let mut value = 0.0;
+                for py in 0..kernel.height {
+                    for px in 0..kernel.width {
+                        value += kernel.data[py * kernel.width + px] * sample(input, ox + px, oy + py, offset);
+                    }
+                }
+                output[oy * width + ox] += value;
+            }
+        }
+    }
+}
+
+/// im2col without building the window matrix: the GEMM reads every window straight out of a zero padded copy of the input,
+/// keeping an `R x NR` tile of outputs in registers while it walks over the kernel taps
+fn im2col(input: Plane, kernels: &[Plane], offset: usize, outputs: &mut [&mut [f32]], width: usize, height: usize) {
+    let (kernel_width, kernel_height) = (kernels[0].width, kernels[0].height);
+    // wide enough that the last tile of a row can read all of its `NR` lanes
+    let padded_width = width.div_ceil(NR) * NR + kernel_width - 1;
+    let padded_height = height + kernel_height - 1;
+    let mut padded = vec![0.0; padded_width * padded_height];
+    for (y, row) in input.data.chunks_exact(input.width.max(1)).enumerate().take(padded_height.saturating_sub(offset)) {
+        let start = (y + offset) * padded_width + offset;
+        let length = input.width.min(padded_width.saturating_sub(offset));
+        padded[start..start + length].copy_from_slice(&row[..length]);
+    }
+
+    for (kernel, output) in kernels.iter().zip(outputs.iter_mut()) {
+        let mut row = 0;
+        while row < height {
+            if height - row >= ROWS {
+                tile::<ROWS>(kernel, &padded, padded_width, output, width, row);
+                row += ROWS;
+            } else {
+                tile::<1>(kernel, &padded, padded_width, output, width, row);
+                row += 1;
+            }
+        }
+    }
+}
+const ROWS: usize = 4;
+const NR: usize = 8;
+
+/// Correlates `R` output rows starting at `row`, `NR` columns at a time
+#[inline(always)]
+fn tile<const R: usize>(kernel: &Plane, padded: &[f32], padded_width: usize, output: &mut [f32], width: usize, row: usize) {
+    for column in (0..width).step_by(NR) {
+        // summed on its own first, so the rounding matches `direct`
+        let mut tile = [[0.0; NR]; R];
+        for (py, weights) in kernel.data.chunks_exact(kernel.width).take(kernel.height).enumerate() {
+            for (px, weight) in weights.iter().enumerate() {
+                for (r, tile) in tile.iter_mut().enumerate() {
+                    let start = (row + r + py) * padded_width + column + px;
+                    let window: &[f32; NR] = padded[start..start + NR].try_into().unwrap();
+                    for (value, sample) in tile.iter_mut().zip(window) {
+                        *value += weight * sample;
+                    }
+                }
+            }
+        }
+        for (r, tile) in tile.iter().enumerate() {
+            let output = &mut output[(row + r) * width + column..(row + r + 1) * width];
+            for (output, value) in output.iter_mut().zip(tile) {
+                *output += value;
+            }
+        }
+    }
+}
+
+/// Sizes of the zero padded transforms, big enough that the circular correlation never wraps into a result
+fn fft_size(input: Plane, kernel: Plane, offset: usize, width: usize, height: usize) -> (usize, usize) {
+    let size = |input: usize, kernel: usize, output: usize| {
+        (input + offset).max((kernel + output).saturating_sub(offset + 1)).max(input).max(kernel).next_power_of_two()
+    };
+    (size(input.width, kernel.width, width), size(input.height, kernel.height, height))
+}
+
+/// Multiplies the input's transform with the conjugate of each kernel's, which correlates them
+fn fft(input: Plane, kernels: &[Plane], offset: usize, outputs: &mut [&mut [f32]], width: usize, height: usize) {
+    let (size_x, size_y) = fft_size(input, kernels[0], offset, width, height);
+    let (plan_x, plan_y) = (Fft::new(size_x), Fft::new(size_y));
+    let transformed_input = transform(input, &plan_x, &plan_y);
+    let scale = 1.0 / (size_x * size_y) as f64;
+    // only the rows holding results need the inverse row transform
+    let rows = (0..height).map(|oy| (oy + size_y - offset) % size_y).collect::<Vec<_>>();
+
+    for (kernel, output) in kernels.iter().zip(outputs.iter_mut()) {
+        let mut product = transform(*kernel, &plan_x, &plan_y);
+        for (product, input) in product.iter_mut().zip(&transformed_input) {
+            *product = input.mul(product.conj());
+        }
+        transform_columns(&mut product, &plan_x, &plan_y, true);
+        for (oy, &y) in rows.iter().enumerate() {
+            let row = &mut product[y * size_x..(y + 1) * size_x];
+            plan_x.run(row, true);
+            for ox in 0..width {
+                output[oy * width + ox] += (row[(ox + size_x - offset) % size_x].re * scale) as f32;
+            }
+        }
+    }
+}
+
+/// The 2D transform of `plane` zero padded to the plans' sizes
+fn transform(plane: Plane, plan_x: &Fft, plan_y: &Fft) -> Vec<Complex> {
+    let size_x = plan_x.size;
+    let mut values = vec![Complex::default(); size_x * plan_y.size];
+    // the padding rows are zero and stay zero, so only the rows of the plane need transforming
+    for (row, data) in values.chunks_exact_mut(size_x).zip(plane.data.chunks_exact(plane.width)) {
+        for (value, data) in row.iter_mut().zip(data) {
+            value.re = *data as f64;
+        }
+        plan_x.run(row, false);
+    }
+    transform_columns(&mut values, plan_x, plan_y, false);
+    values
+}
+
+fn transform_columns(values: &mut [Complex], plan_x: &Fft, plan_y: &Fft, inverse: bool) {
+    let mut column = vec![Complex::default(); plan_y.size];
+    for x in 0..plan_x.size {
+        for (y, value) in column.iter_mut().enumerate() {
+            *value = values[y * plan_x.size + x];
+        }
+        plan_y.run(&mut column, inverse);
+        for (y, value) in column.iter().enumerate() {
+            values[y * plan_x.size + x] = *value;
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    #[inline]
+    fn mul(self, other: Self) -> Self {
+        Self { re: self.re * other.re - self.im * other.im, im: self.re * other.im + self.im * other.re }
+    }
+    #[inline]
+    fn conj(self) -> Self {
+        Self { re: self.re, im: -self.im }
+    }
+}
+
+/// Iterative radix-2 FFT of a fixed power of two length, the inverse is left unscaled
+struct Fft {
+    size: usize,
+    /// the twiddles `e^(-iπk/h)` of every level back to back, level `h` (half the butterfly span) starts at `h - 1`
+    twiddles: Vec<Complex>,
+    inverse_twiddles: Vec<Complex>,
+    /// index pairs swapped by the bit reversal permutation
+    swaps: Vec<(usize, usize)>,
+}
+
+impl Fft {
+    fn new(size: usize) -> Self {
+        let mut twiddles = Vec::with_capacity(size);
+        let mut half = 1;
+        while half < size {
+            twiddles.extend((0..half).map(|k| {
+                let angle = -PI * k as f64 / half as f64;
+                Complex { re: angle.cos(), im: angle.sin() }
+            }));
+            half *= 2;
+        }
+        let inverse_twiddles = twiddles.iter().map(|x| x.conj()).collect();
+        let bits = size.trailing_zeros();
+        let swaps = (0..size).filter_map(|i| {
+            let j = i.reverse_bits().checked_shr(usize::BITS - bits).unwrap_or(0);
+            (i < j).then_some((i, j))
+        }).collect();
+        Self { size, twiddles, inverse_twiddles, swaps }
+    }
+    fn run(&self, values: &mut [Complex], inverse: bool) {
+        for &(i, j) in &self.swaps {
+            values.swap(i, j);
+        }
+        let twiddles = if inverse { &self.inverse_twiddles } else { &self.twiddles };
+        let mut half = 1;
+        while half < self.size {
+            let twiddles = &twiddles[half - 1..2 * half - 1];
+            for block in values.chunks_exact_mut(2 * half) {
+                let (even, odd) = block.split_at_mut(half);
+                for ((even, odd), twiddle) in even.iter_mut().zip(odd.iter_mut()).zip(twiddles) {
+                    let product = odd.mul(*twiddle);
+                    *odd = Complex { re: even.re - product.re, im: even.im - product.im };
+                    *even = Complex { re: even.re + product.re, im: even.im + product.im };
+                }
+            }
+            half *= 2;
+        }
+    }
+}
@@ -1,14 +1,20 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 use crate::array::Array1D;
 
+/// A loss, held by the `Network`
 pub trait CostFunction<P, E> {
-    fn cost(predicted: &P, expected: &E) -> f32;
-    fn derivative(predicted: &P, expected: &E) -> P;
+    fn cost(&self, predicted: &P, expected: &E) -> f32;
+    fn derivative(&self, predicted: &P, expected: &E) -> P;
 }
 
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
 pub struct Mse;
 impl<const I: usize> CostFunction<Array1D<I>, Array1D<I>> for Mse {
-    fn cost(predicted: &Array1D<I>, expected: &Array1D<I>) -> f32 {
+    fn cost(&self, predicted: &Array1D<I>, expected: &Array1D<I>) -> f32 {
         let mut result = 0.0;
         for (p, e) in predicted.iter().zip(expected.iter()) {
             result += (*p - *e).powi(2)
@@ -16,7 +22,7 @@ impl<const I: usize> CostFunction<Array1D<I>, Array1D<I>> for Mse {
         result / I as f32
     }
 
-    fn derivative(predicted: &Array1D<I>, expected: &Array1D<I>) -> Array1D<I> {
+    fn derivative(&self, predicted: &Array1D<I>, expected: &Array1D<I>) -> Array1D<I> {
         let mut result = Array1D::new();
         for (r, (p, e)) in result.iter_mut().zip(predicted.iter().zip(expected.iter())) {
             *r = 2.0 * (*p - *e);
@@ -25,7 +31,7 @@ impl<const I: usize> CostFunction<Array1D<I>, Array1D<I>> for Mse {
     }
 }
 impl<const I: usize> CostFunction<Array1D<I>, usize> for Mse {
-    fn cost(predicted: &Array1D<I>, expected: &usize) -> f32 {
+    fn cost(&self, predicted: &Array1D<I>, expected: &usize) -> f32 {
         let mut result = 0.0;
         for (i, p) in predicted.iter().enumerate() {
             result += (*p - ((i == *expected) as u32 as f32)).powi(2)
@@ -33,7 +39,7 @@ impl<const I: usize> CostFunction<Array1D<I>, usize> for Mse {
         result / I as f32
     }
 
-    fn derivative(predicted: &Array1D<I>, expected: &usize) -> Array1D<I> {
+    fn derivative(&self, predicted: &Array1D<I>, expected: &usize) -> Array1D<I> {
         let mut result = Array1D::new();
         for (r, (i, p)) in result.iter_mut().zip(predicted.iter().enumerate()) {
             *r = 2.0 * (*p - ((i == *expected) as u32 as f32));
@@ -42,30 +48,464 @@ impl<const I: usize> CostFunction<Array1D<I>, usize> for Mse {
     }
 }
 
+/// Mean absolute error, the L1 loss
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Mae;
+impl<const I: usize> CostFunction<Array1D<I>, Array1D<I>> for Mae {
+    fn cost(&self, predicted: &Array1D<I>, expected: &Array1D<I>) -> f32 {
+        predicted.iter().zip(expected.iter()).map(|(p, e)| (p - e).abs()).sum::<f32>() / I as f32
+    }
+
+    fn derivative(&self, predicted: &Array1D<I>, expected: &Array1D<I>) -> Array1D<I> {
+        elementwise_derivative(predicted, expected, |difference| {
+            if difference == 0.0 { 0.0 } else { difference.signum() }
+        })
+    }
+}
+
+/// Squared error up to `delta`, absolute error beyond it
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Huber {
+    pub delta: f32,
+}
+impl Default for Huber {
+    fn default() -> Self {
+        Self { delta: 1.0 }
+    }
+}
+impl Huber {
+    /// with a delta of 1
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_delta(delta: f32) -> Self {
+        Self { delta }
+    }
+}
+impl<const I: usize> CostFunction<Array1D<I>, Array1D<I>> for Huber {
+    fn cost(&self, predicted: &Array1D<I>, expected: &Array1D<I>) -> f32 {
+        predicted.iter().zip(expected.iter()).map(|(p, e)| {
+            let difference = (p - e).abs();
+            if difference <= self.delta {
+                0.5 * difference * difference
+            } else {
+                self.delta * (difference - 0.5 * self.delta)
+            }
+        }).sum::<f32>() / I as f32
+    }
+
+    fn derivative(&self, predicted: &Array1D<I>, expected: &Array1D<I>) -> Array1D<I> {
+        elementwise_derivative(predicted, expected, |difference| difference.clamp(-self.delta, self.delta))
+    }
+}
+
+/// `ln(cosh(p - e))`
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct LogCosh;
+impl<const I: usize> CostFunction<Array1D<I>, Array1D<I>> for LogCosh {
+    fn cost(&self, predicted: &Array1D<I>, expected: &Array1D<I>) -> f32 {
+        predicted.iter().zip(expected.iter()).map(|(p, e)| {
+            // `cosh` overflows long before its logarithm does
+            let difference = (p - e).abs();
+            difference + (-2.0 * difference).exp().ln_1p() - std::f32::consts::LN_2
+        }).sum::<f32>() / I as f32
+    }
+
+    fn derivative(&self, predicted: &Array1D<I>, expected: &Array1D<I>) -> Array1D<I> {
+        elementwise_derivative(predicted, expected, f32::tanh)
+    }
+}
+
+/// Pinball loss, minimal at `quantile` of the targets
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantile {
+    pub quantile: f32,
+}
+impl Default for Quantile {
+    fn default() -> Self {
+        Self { quantile: 0.5 }
+    }
+}
+impl Quantile {
+    /// the median
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_quantile(quantile: f32) -> Self {
+        Self { quantile }
+    }
+}
+impl<const I: usize> CostFunction<Array1D<I>, Array1D<I>> for Quantile {
+    fn cost(&self, predicted: &Array1D<I>, expected: &Array1D<I>) -> f32 {
+        predicted.iter().zip(expected.iter()).map(|(p, e)| {
+            let difference = e - p;
+            (self.quantile * difference).max((self.quantile - 1.0) * difference)
+        }).sum::<f32>() / I as f32
+    }
+
+    fn derivative(&self, predicted: &Array1D<I>, expected: &Array1D<I>) -> Array1D<I> {
+        elementwise_derivative(predicted, expected, |difference| {
+            if difference > 0.0 {
+                1.0 - self.quantile
+            } else if difference < 0.0 {
+                -self.quantile
+            } else {
+                0.0
+            }
+        })
+    }
+}
+
+/// The derivative of a mean over `I` elements, `derivative` takes `p - e`
+fn elementwise_derivative<const I: usize>(predicted: &Array1D<I>, expected: &Array1D<I>, derivative: impl Fn(f32) -> f32) -> Array1D<I> {
+    let mut result = Array1D::new();
+    for (r, (p, e)) in result.iter_mut().zip(predicted.iter().zip(expected.iter())) {
+        *r = derivative(p - e) / I as f32;
+    }
+    result
+}
+
+/// `ln(Σ exp(x))`, shifted by the largest value so big logits don't overflow
+pub fn log_sum_exp<const I: usize>(values: &Array1D<I>) -> f32 {
+    let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    if !max.is_finite() {
+        return max;
+    }
+    max + values.iter().map(|value| (value - max).exp()).sum::<f32>().ln()
+}
+pub fn softmax<const I: usize>(values: &Array1D<I>) -> Array1D<I> {
+    let mut result = log_softmax(values);
+    for r in result.iter_mut() {
+        *r = r.exp();
+    }
+    result
+}
+pub fn log_softmax<const I: usize>(values: &Array1D<I>) -> Array1D<I> {
+    let total = log_sum_exp(values);
+    let mut result = Array1D::new();
+    if !total.is_finite() {
+        // every value is -inf or some are inf, the probability is split evenly between the largest ones
+        let count = values.iter().filter(|value| **value == total).count();
+        for (r, value) in result.iter_mut().zip(values.iter()) {
+            *r = if *value == total { -(count as f32).ln() } else { f32::NEG_INFINITY };
+        }
+        return result;
+    }
+    for (r, value) in result.iter_mut().zip(values.iter()) {
+        *r = value - total;
+    }
+    result
+}
+
+/// Softmax cross-entropy on logits, with either a class index or a target distribution
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
 pub struct CrossEntropy;
 impl CrossEntropy {
-    fn softmax<const I: usize>(values: &Array1D<I>) -> Array1D<I> {
-        let mut result = Array1D::new();
-        let mut total = 0.0;
-        for value in values.iter() {
-            total += value.exp();
+    pub fn new() -> Self {
+        Self
+    }
+    /// a `CustomCrossEntropy` that scales the loss of every class by its weight
+    pub fn with_class_weights(self, class_weights: Vec<f32>) -> CustomCrossEntropy {
+        CustomCrossEntropy::new().with_class_weights(class_weights)
+    }
+    /// a `CustomCrossEntropy` with smoothed labels
+    pub fn with_label_smoothing(self, label_smoothing: f32) -> CustomCrossEntropy {
+        CustomCrossEntropy::new().with_label_smoothing(label_smoothing)
+    }
+}
+impl<const I: usize> CostFunction<Array1D<I>, usize> for CrossEntropy {
+    fn cost(&self, predicted: &Array1D<I>, expected: &usize) -> f32 {
+        CustomCrossEntropy::new().cost(predicted, expected)
+    }
+
+    fn derivative(&self, predicted: &Array1D<I>, expected: &usize) -> Array1D<I> {
+        CustomCrossEntropy::new().derivative(predicted, expected)
+    }
+}
+impl<const I: usize> CostFunction<Array1D<I>, Array1D<I>> for CrossEntropy {
+    fn cost(&self, predicted: &Array1D<I>, expected: &Array1D<I>) -> f32 {
+        CustomCrossEntropy::new().cost(predicted, expected)
+    }
+
+    fn derivative(&self, predicted: &Array1D<I>, expected: &Array1D<I>) -> Array1D<I> {
+        CustomCrossEntropy::new().derivative(predicted, expected)
+    }
+}
+
+/// `CrossEntropy` with class weights and label smoothing
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CustomCrossEntropy {
+    /// the loss of every class is scaled by its weight, empty weighs all classes 1
+    pub class_weights: Vec<f32>,
+    /// moves this share of the target onto a uniform distribution
+    pub label_smoothing: f32,
+}
+impl CustomCrossEntropy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// panics unless there's a finite, non-negative weight for every class
+    pub fn with_class_weights(self, class_weights: Vec<f32>) -> Self {
+        check_class_weights(&class_weights);
+        Self { class_weights, ..self }
+    }
+    pub fn with_label_smoothing(self, label_smoothing: f32) -> Self {
+        Self { label_smoothing, ..self }
+    }
+    /// the smoothed target with the class weights folded in
+    fn weighted_target<const I: usize>(&self, expected: &Array1D<I>) -> Array1D<I> {
+        let total = expected.iter().sum::<f32>();
+        let mut result = expected.clone();
+        for (i, r) in result.iter_mut().enumerate() {
+            if self.label_smoothing != 0.0 {
+                *r = *r * (1.0 - self.label_smoothing) + total * self.label_smoothing / I as f32;
+            }
+            *r *= class_weight::<I>(&self.class_weights, i);
         }
-        for (r, value) in result.iter_mut().zip(values.iter()) {
-            *r = value.exp() / total;
+        result
+    }
+}
+fn check_class_weights(class_weights: &[f32]) {
+    assert!(!class_weights.is_empty(), "class weights can't be empty");
+    assert!(class_weights.iter().all(|w| w.is_finite() && *w >= 0.0), "class weights must be finite and non-negative: {class_weights:?}");
+}
+fn class_weight<const I: usize>(class_weights: &[f32], class: usize) -> f32 {
+    assert!(class_weights.is_empty() || class_weights.len() == I, "{} class weights for {I} classes", class_weights.len());
+    class_weights.get(class).copied().unwrap_or(1.0)
+}
+fn one_hot<const I: usize>(class: usize) -> Array1D<I> {
+    debug_assert!(I > class);
+    let mut result = Array1D::new();
+    result[class] = 1.0;
+    result
+}
+impl<const I: usize> CostFunction<Array1D<I>, usize> for CustomCrossEntropy {
+    fn cost(&self, predicted: &Array1D<I>, expected: &usize) -> f32 {
+        self.cost(predicted, &one_hot(*expected))
+    }
+
+    fn derivative(&self, predicted: &Array1D<I>, expected: &usize) -> Array1D<I> {
+        self.derivative(predicted, &one_hot(*expected))
+    }
+}
+impl<const I: usize> CostFunction<Array1D<I>, Array1D<I>> for CustomCrossEntropy {
+    fn cost(&self, predicted: &Array1D<I>, expected: &Array1D<I>) -> f32 {
+        let mut result = 0.0;
+        for (p, e) in log_softmax(predicted).iter().zip(self.weighted_target(expected).iter()) {
+            // skip zero targets so `0 * -inf` doesn't turn into NaN
+            if *e != 0.0 {
+                result -= *e * *p;
+            }
+        }
+        result
+    }
+
+    fn derivative(&self, predicted: &Array1D<I>, expected: &Array1D<I>) -> Array1D<I> {
+        let target = self.weighted_target(expected);
+        let total = target.iter().sum::<f32>();
+        let mut result = softmax(predicted);
+        for (r, e) in result.iter_mut().zip(target.iter()) {
+            *r = *r * total - *e;
         }
         result
     }
 }
-impl<const I: usize> CostFunction<Array1D<I>, usize> for CrossEntropy {
-    fn cost(predicted: &Array1D<I>, expected: &usize) -> f32 {
-        -Self::softmax(predicted)[*expected].ln()
+
+/// Softmax focal loss on logits, `-w (1 - p)^gamma ln(p)`, a `gamma` of 0 is `CrossEntropy`
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FocalLoss {
+    pub gamma: f32,
+    /// the weight `w` of every class, empty weighs all classes 1
+    pub class_weights: Vec<f32>,
+}
+impl Default for FocalLoss {
+    fn default() -> Self {
+        Self { gamma: 2.0, class_weights: Vec::new() }
+    }
+}
+impl FocalLoss {
+    /// with a gamma of 2
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_gamma(self, gamma: f32) -> Self {
+        Self { gamma, ..self }
+    }
+    /// panics unless there's a finite, non-negative weight for every class
+    pub fn with_class_weights(self, class_weights: Vec<f32>) -> Self {
+        check_class_weights(&class_weights);
+        Self { class_weights, ..self }
+    }
+}
+impl<const I: usize> CostFunction<Array1D<I>, usize> for FocalLoss {
+    fn cost(&self, predicted: &Array1D<I>, expected: &usize) -> f32 {
+        let log_p = log_softmax(predicted)[*expected];
+        -class_weight::<I>(&self.class_weights, *expected) * (-log_p.exp_m1()).powf(self.gamma) * log_p
     }
 
-    fn derivative(predicted: &Array1D<I>, expected: &usize) -> Array1D<I> {
+    fn derivative(&self, predicted: &Array1D<I>, expected: &usize) -> Array1D<I> {
         debug_assert!(I > *expected);
-        let mut softmax = Self::softmax(predicted);
-        softmax[*expected] -= 1.0;
-        softmax
+        let log_p = log_softmax(predicted)[*expected];
+        let (p, q) = (log_p.exp(), -log_p.exp_m1());
+        // the derivative by `ln(p)`, `p ln(p)` vanishes faster than `(1 - p)^(gamma - 1)` blows up
+        let focusing = if q > 0.0 { self.gamma * q.powf(self.gamma - 1.0) * p * log_p } else { 0.0 };
+        let scale = class_weight::<I>(&self.class_weights, *expected) * (focusing - q.powf(self.gamma));
+        // times `one_hot - softmax`, the derivative of `ln(p)` by the logits
+        let mut result = softmax(predicted);
+        result[*expected] -= 1.0;
+        result *= -scale;
+        result
+    }
+}
+
+/// Negative log-likelihood on log-probabilities, like the output of `LogSoftmax`
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Nll;
+impl<const I: usize> CostFunction<Array1D<I>, usize> for Nll {
+    fn cost(&self, predicted: &Array1D<I>, expected: &usize) -> f32 {
+        -predicted[*expected]
+    }
+
+    fn derivative(&self, _predicted: &Array1D<I>, expected: &usize) -> Array1D<I> {
+        debug_assert!(I > *expected);
+        let mut result = Array1D::new();
+        result[*expected] = -1.0;
+        result
     }
-}
\ No newline at end of file
+}
+impl<const I: usize> CostFunction<Array1D<I>, Array1D<I>> for Nll {
+    fn cost(&self, predicted: &Array1D<I>, expected: &Array1D<I>) -> f32 {
+        let mut result = 0.0;
+        for (p, e) in predicted.iter().zip(expected.iter()) {
+            if *e != 0.0 {
+                result -= *e * *p;
+            }
+        }
+        result
+    }
+
+    fn derivative(&self, _predicted: &Array1D<I>, expected: &Array1D<I>) -> Array1D<I> {
+        let mut result = expected.clone();
+        result *= -1.0;
+        result
+    }
+}
+
+#[test]
+fn cross_entropy_large_logits() {
+    let predicted = Array1D::<3>::from([1000.0, -1000.0, 0.0].as_slice());
+    assert_eq!(CrossEntropy::new().cost(&predicted, &0), 0.0);
+    assert!((CrossEntropy::new().cost(&predicted, &1) - 2000.0).abs() < 1e-3);
+    assert!(CrossEntropy::new().derivative(&predicted, &2).iter().all(|x| x.is_finite()));
+
+    let expected = Array1D::<3>::from([0.5, 0.0, 0.5].as_slice());
+    assert!((CrossEntropy::new().cost(&predicted, &expected) - 500.0).abs() < 1e-3);
+    let derivative = CrossEntropy::new().derivative(&predicted, &expected);
+    assert!((derivative[0] - 0.5).abs() < 1e-6 && (derivative[2] + 0.5).abs() < 1e-6);
+}
+
+#[test]
+fn softmax_of_infinite_logits() {
+    let masked = Array1D::<4>::from([f32::NEG_INFINITY; 4].as_slice());
+    assert_eq!(log_sum_exp(&masked), f32::NEG_INFINITY);
+    assert!(softmax(&masked).iter().all(|x| *x == 0.25));
+    let infinite = Array1D::<3>::from([f32::INFINITY, 0.0, f32::INFINITY].as_slice());
+    assert_eq!(log_sum_exp(&infinite), f32::INFINITY);
+    assert!(softmax(&infinite).iter().eq([0.5, 0.0, 0.5].iter()));
+}
+
+#[test]
+fn configured_costs_match_numeric() {
+    let predicted = Array1D::<4>::from([0.3, -1.2, 2.0, 0.1].as_slice());
+    let check = |cost: &dyn Fn(&Array1D<4>) -> f32, derivative: Array1D<4>| {
+        let check = crate::gradcheck::check_function(&predicted, 1e-2, cost, &derivative);
+        assert!(check.max_error() < 1e-3, "{check:?}");
+    };
+
+    let weighted = CrossEntropy::new().with_class_weights(vec![1.0, 9.0, 0.5, 2.0]).with_label_smoothing(0.1);
+    check(&|x| weighted.cost(x, &1), weighted.derivative(&predicted, &1));
+    let target = Array1D::from([0.2, 0.0, 0.8, 0.0].as_slice());
+    check(&|x| weighted.cost(x, &target), weighted.derivative(&predicted, &target));
+
+    // the weight of the expected class scales the whole loss
+    let plain = CrossEntropy::new();
+    let weighted = CrossEntropy::new().with_class_weights(vec![1.0, 9.0, 0.5, 2.0]);
+    assert!((weighted.cost(&predicted, &1) - 9.0 * plain.cost(&predicted, &1)).abs() < 1e-4);
+
+    for gamma in [0.0, 0.5, 2.0] {
+        let focal = FocalLoss::new().with_gamma(gamma).with_class_weights(vec![0.25, 1.0, 1.0, 0.5]);
+        for class in 0..4 {
+            check(&|x| focal.cost(x, &class), focal.derivative(&predicted, &class));
+        }
+    }
+    assert_eq!(FocalLoss::new().with_gamma(0.0).cost(&predicted, &2), plain.cost(&predicted, &2));
+    let confident = Array1D::<4>::from([100.0, -100.0, 0.0, 0.0].as_slice());
+    assert!(FocalLoss::new().with_gamma(0.5).derivative(&confident, &0).iter().all(|x| x.is_finite()));
+}
+
+#[test]
+fn regression_costs_match_numeric() {
+    // no difference lands on a kink
+    let predicted = Array1D::<5>::from([0.3, -1.2, 2.0, 0.1, 4.0].as_slice());
+    let expected = Array1D::<5>::from([0.5, -3.0, 2.4, -0.2, -1.0].as_slice());
+    let check = |cost: &dyn CostFunction<Array1D<5>, Array1D<5>>| {
+        let check = crate::gradcheck::check_function(&predicted, 1e-2, |x| cost.cost(x, &expected), &cost.derivative(&predicted, &expected));
+        assert!(check.max_error() < 1e-3, "{check:?}");
+    };
+    check(&Mae);
+    check(&Huber::with_delta(0.7));
+    check(&LogCosh);
+    check(&Quantile::with_quantile(0.9));
+
+    assert!((Huber::new().cost(&predicted, &expected) - (0.02 + 1.3 + 0.08 + 0.045 + 4.5) / 5.0).abs() < 1e-5);
+    assert!((Quantile::new().cost(&predicted, &expected) * 2.0 - Mae.cost(&predicted, &expected)).abs() < 1e-5);
+    let far = Array1D::<1>::from([1000.0].as_slice());
+    assert!((LogCosh.cost(&far, &Array1D::new()) - (1000.0 - std::f32::consts::LN_2)).abs() < 1e-3);
+}
+
+#[test]
+#[should_panic(expected = "3 class weights for 4 classes")]
+fn class_weights_must_match_classes() {
+    CrossEntropy::new().with_class_weights(vec![1.0, 2.0, 3.0]).cost(&Array1D::<4>::new(), &0);
+}
+
+#[test]
+#[should_panic(expected = "3 class weights for 4 classes")]
+fn focal_class_weights_must_match_classes() {
+    FocalLoss::new().with_class_weights(vec![1.0, 2.0, 3.0]).derivative(&Array1D::<4>::new(), &0);
+}
+
+#[cfg(feature = "rkyv")]
+#[test]
+fn networks_archive_like_before_costs() {
+    use crate::{layer::dense::DenseLayer, Network};
+
+    // the layout `Network` was saved with before it held its cost
+    #[derive(rkyv::Archive, rkyv::Serialize)]
+    struct Saved {
+        layer: DenseLayer<3, 2>,
+    }
+    let network = Network::<_, _, CrossEntropy, Array1D<2>, usize>::new(DenseLayer::<3, 2>::random());
+    let saved = Saved { layer: network.layer.clone() };
+    let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&saved).unwrap();
+    assert_eq!(rkyv::to_bytes::<rkyv::rancor::Error>(&network).unwrap().as_slice(), bytes.as_slice());
+
+    let loaded = rkyv::from_bytes::<Network<_, DenseLayer<3, 2>, CrossEntropy, Array1D<2>, usize>, rkyv::rancor::Error>(&bytes).unwrap();
+    assert!(loaded.layer.weights.iter().flatten().eq(network.layer.weights.iter().flatten()));
+}
@@ -0,0 +1,108 @@
+//! Cache-blocked matrix multiplication used by the batched layer paths.
+//!
+//! Every element of the result receives its products in ascending `k` order,
+//! so the output is bit-for-bit the same as the naive per-sample loops.
+//! The register tile loops work on fixed-size arrays, which LLVM turns into SIMD.
+
+/// block sizes, a packed `KC x NC` block of `b` is 128KiB and stays in L2
+const KC: usize = 128;
+const NC: usize = 256;
+/// size of the tile of `c` kept in registers
+const MR: usize = 4;
+const NR: usize = 8;
+
+/// A strided view of a row-major buffer, element `(row, column)` is `data[row * row_stride + column * column_stride]`
+#[derive(Clone, Copy)]
+pub(crate) struct Matrix<'a> {
+    pub data: &'a [f32],
+    pub row_stride: usize,
+    pub column_stride: usize,
+}
+
+impl<'a> Matrix<'a> {
+    /// a `rows x columns` matrix stored row by row
+    pub fn rows(data: &'a [f32], columns: usize) -> Self {
+        Self { data, row_stride: columns, column_stride: 1 }
+    }
+    /// the transpose of `Matrix::rows(data, columns)`
+    pub fn columns(data: &'a [f32], columns: usize) -> Self {
+        Self { data, row_stride: 1, column_stride: columns }
+    }
+    #[inline]
+    fn get(&self, row: usize, column: usize) -> f32 {
+        self.data[row * self.row_stride + column * self.column_stride]
+    }
+}
+
+/// `c += a * b`, where `a` is `m x k`, `b` is `k x n` and `c` is a row-major `m x n` buffer
+pub(crate) fn gemm(m: usize, n: usize, k: usize, a: Matrix, b: Matrix, c: &mut [f32]) {
+    assert_eq!(c.len(), m * n);
+    let mut packed_b = vec![0.0; KC.min(k) * NC.min(n)];
+    let mut packed_a = vec![0.0; KC.min(k) * MR];
+    for k0 in (0..k).step_by(KC) {
+        let kc = KC.min(k - k0);
+        for n0 in (0..n).step_by(NC) {
+            let nc = NC.min(n - n0);
+            // copy the block of `b` into contiguous rows, so the kernel reads it in order whatever its layout
+            for kk in 0..kc {
+                for (j, packed) in packed_b[kk * nc..(kk + 1) * nc].iter_mut().enumerate() {
+                    *packed = b.get(k0 + kk, n0 + j);
+                }
+            }
+            let packed_b = &packed_b[..kc * nc];
+
+            let mut row = 0;
+            while row < m {
+                // leftover rows go one at a time
+                let rows = if m - row >= MR { MR } else { 1 };
+                for kk in 0..kc {
+                    for r in 0..rows {
+                        packed_a[kk * rows + r] = a.get(row + r, k0 + kk);
+                    }
+                }
+                let packed_a = &packed_a[..kc * rows];
+                if rows == MR {
+                    kernel::<MR>(packed_a, packed_b, kc, nc, &mut c[row * n..], n, n0);
+                } else {
+                    kernel::<1>(packed_a, packed_b, kc, nc, &mut c[row * n..], n, n0);
+                }
+                row += rows;
+            }
+        }
+    }
+}
+
+/// Adds the product of `R` packed rows of `a` and the packed block of `b` into `c`,
+/// keeping an `R x NR` tile of `c` in registers for the whole `kc` loop
+#[inline(always)]
+fn kernel<const R: usize>(a: &[f32], b: &[f32], kc: usize, nc: usize, c: &mut [f32], n: usize, n0: usize) {
+    let mut j = 0;
+    while j + NR <= nc {
+        let mut tile = [[0.0; NR]; R];
+        for (r, tile) in tile.iter_mut().enumerate() {
+            tile.copy_from_slice(&c[r * n + n0 + j..r * n + n0 + j + NR]);
+        }
+        for kk in 0..kc {
+            let b: &[f32; NR] = b[kk * nc + j..kk * nc + j + NR].try_into().unwrap();
+            for (r, tile) in tile.iter_mut().enumerate() {
+                let a = a[kk * R + r];
+                for (c, b) in tile.iter_mut().zip(b) {
+                    *c += a * b;
+                }
+            }
+        }
+        for (r, tile) in tile.iter().enumerate() {
+            c[r * n + n0 + j..r * n + n0 + j + NR].copy_from_slice(tile);
+        }
+        j += NR;
+    }
+    for j in j..nc {
+        for r in 0..R {
+            let mut value = c[r * n + n0 + j];
+            for kk in 0..kc {
+                value += a[kk * R + r] * b[kk * nc + j];
+            }
+            c[r * n + n0 + j] = value;
+        }
+    }
+}
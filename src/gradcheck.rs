@@ -0,0 +1,128 @@
+use crate::{array::Tensor, layer::Layer};
+
+/// A gradient from `backward` next to its central finite difference
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Difference {
+    pub analytic: f32,
+    pub numeric: f32,
+}
+impl Difference {
+    /// `|a - n| / max(|a|, |n|)`, the absolute error for gradients smaller than 1
+    pub fn relative_error(&self) -> f32 {
+        (self.analytic - self.numeric).abs() / self.analytic.abs().max(self.numeric.abs()).max(1.0)
+    }
+}
+
+/// One difference per input element and per parameter, in the order of their `Tensor::slices`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GradCheck {
+    pub input: Vec<Difference>,
+    pub parameters: Vec<Difference>,
+}
+impl GradCheck {
+    pub fn max_error(&self) -> f32 {
+        self.input.iter().chain(&self.parameters).map(Difference::relative_error).fold(0.0, f32::max)
+    }
+}
+
+/// Compares `backward` against central finite differences of `loss`, `derivative` is the gradient of `loss` with respect to the output.
+/// Parameters are nudged through `apply_gradients`, layers with randomness (like dropout in training mode) won't pass.
+pub fn check<I, L>(layer: &L, input: &I, epsilon: f32, loss: impl Fn(&L::Output) -> f32, derivative: impl Fn(&L::Output) -> L::Output) -> GradCheck
+where
+    I: Tensor + Clone,
+    L: Layer<I> + Clone,
+    L::Gradients: Clone, {
+    let (output, forward_data) = layer.forward(input.clone());
+    let (input_gradients, gradients) = layer.backward(derivative(&output), forward_data);
+    let evaluate = |layer: &L, input: I| loss(&layer.forward(input).0);
+
+    let mut result = GradCheck::default();
+    let mut nudged = input.clone();
+    for (i, (analytic, original)) in input_gradients.slices().flatten().zip(input.slices().flatten()).enumerate() {
+        nudge(&mut nudged, i, original + epsilon);
+        let above = evaluate(layer, nudged.clone());
+        nudge(&mut nudged, i, original - epsilon);
+        let below = evaluate(layer, nudged.clone());
+        nudge(&mut nudged, i, *original);
+        result.input.push(Difference { analytic: *analytic, numeric: (above - below) / (2.0 * epsilon) });
+    }
+
+    let mut layer = layer.clone();
+    for (i, analytic) in gradients.slices().flatten().enumerate() {
+        let mut direction = L::Gradients::zeroed();
+        nudge(&mut direction, i, 1.0);
+        layer.apply_gradients(direction.clone(), epsilon);
+        let above = evaluate(&layer, input.clone());
+        layer.apply_gradients(direction.clone(), -2.0 * epsilon);
+        let below = evaluate(&layer, input.clone());
+        layer.apply_gradients(direction, epsilon);
+        result.parameters.push(Difference { analytic: *analytic, numeric: (above - below) / (2.0 * epsilon) });
+    }
+    result
+}
+
+/// `check` with the loss `Σ w_i * y_i` for fixed weights that are different for every output
+pub fn check_linear<I, L>(layer: &L, input: &I, epsilon: f32) -> GradCheck
+where
+    I: Tensor + Clone,
+    L: Layer<I> + Clone,
+    L::Gradients: Clone,
+    L::Output: Tensor, {
+    let weight = |i: usize| (i as f32 * 1.7 + 0.3).sin();
+    check(layer, input, epsilon, |output| {
+        output.slices().flatten().enumerate().map(|(i, x)| x * weight(i)).sum()
+    }, |_| {
+        let mut derivative = L::Output::zeroed();
+        for (i, x) in derivative.slices_mut().flatten().enumerate() {
+            *x = weight(i);
+        }
+        derivative
+    })
+}
+
+/// Compares `derivative` against central finite differences of `f` around `at`, for gradients that don't come from a layer
+pub fn check_function<T: Tensor + Clone>(at: &T, epsilon: f32, f: impl Fn(&T) -> f32, derivative: &T) -> GradCheck {
+    let mut result = GradCheck::default();
+    let mut nudged = at.clone();
+    for (i, (analytic, original)) in derivative.slices().flatten().zip(at.slices().flatten()).enumerate() {
+        nudge(&mut nudged, i, original + epsilon);
+        let above = f(&nudged);
+        nudge(&mut nudged, i, original - epsilon);
+        let below = f(&nudged);
+        nudge(&mut nudged, i, *original);
+        result.input.push(Difference { analytic: *analytic, numeric: (above - below) / (2.0 * epsilon) });
+    }
+    result
+}
+
+fn nudge<T: Tensor>(tensor: &mut T, index: usize, value: f32) {
+    *tensor.slices_mut().flatten().nth(index).unwrap() = value;
+}
+
+#[test]
+fn layers_pass_gradcheck() {
+    use rand::{rngs::StdRng, SeedableRng};
+    use crate::{activation::sigmoid::Sigmoid, array::{Array1D, Array2D, Array3D}, layer::{bias::BiasLayer, convolution::{ChannelConvolution, Convolution}, dense::DenseLayer, pooling::MaxPooling}};
+
+    fn filled<T: Tensor>() -> T {
+        let mut tensor = T::zeroed();
+        for (i, x) in tensor.slices_mut().flatten().enumerate() {
+            *x = (i as f32 * 0.37).sin() + i as f32 * 0.01;
+        }
+        tensor
+    }
+    let rng = &mut StdRng::seed_from_u64(0);
+
+    let checks = [
+        check_linear(&DenseLayer::<5, 3>::random_with_rng(rng), &filled::<Array1D<5>>(), 1e-2),
+        check_linear(&Sigmoid, &filled::<Array1D<5>>(), 1e-2),
+        check_linear(&Convolution::<3>::random_with_rng(rng), &filled::<Array2D<6, 5>>(), 1e-2),
+        check_linear(&BiasLayer::<6, 5>::random_with_rng(rng), &filled::<Array2D<6, 5>>(), 1e-2),
+        check_linear(&MaxPooling::<2, 3, 2>::default(), &filled::<Array2D<6, 4>>(), 1e-3),
+        check_linear(&ChannelConvolution::<3, 2, 3>::random_with_rng(rng), &filled::<Array3D<2, 5, 4>>(), 1e-2),
+        check_linear(&BiasLayer::<5, 4>::random_with_rng(rng), &filled::<Array3D<2, 5, 4>>(), 1e-2),
+    ];
+    for (i, check) in checks.iter().enumerate() {
+        assert!(check.max_error() < 1e-2, "layer {i}: {check:?}");
+    }
+}
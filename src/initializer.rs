@@ -0,0 +1,113 @@
+use rand::Rng;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::array::Tensor;
+
+/// How to fill a layer's parameters, `fan_in` and `fan_out` are the number of inputs and outputs each parameter connects
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Initializer {
+    Zeros,
+    Constant(f32),
+    /// Glorot, for sigmoid and tanh
+    #[default]
+    XavierUniform,
+    XavierNormal,
+    /// Kaiming, for relu
+    HeUniform,
+    HeNormal,
+    /// for selu
+    LeCunUniform,
+    LeCunNormal,
+    /// orthonormal rows (or columns if there are more rows than columns) scaled by a gain
+    Orthogonal(f32),
+}
+
+impl Initializer {
+    pub fn initialize<T: Tensor, R: Rng + ?Sized>(&self, tensor: &mut T, fan_in: usize, fan_out: usize, rng: &mut R) {
+        let len = tensor.slices().map(|x| x.len()).sum::<usize>();
+        let values = self.values(len, fan_in, fan_out, rng);
+        let mut values = values.into_iter();
+        for slice in tensor.slices_mut() {
+            for (x, value) in slice.iter_mut().zip(values.by_ref()) {
+                *x = value;
+            }
+        }
+    }
+    fn values<R: Rng + ?Sized>(&self, len: usize, fan_in: usize, fan_out: usize, rng: &mut R) -> Vec<f32> {
+        let (fan_in, fan_out) = (fan_in as f32, fan_out as f32);
+        let uniform = |limit: f32, rng: &mut R| (0..len).map(|_| (rng.random::<f32>() * 2.0 - 1.0) * limit).collect();
+        match *self {
+            Self::Zeros => vec![0.0; len],
+            Self::Constant(value) => vec![value; len],
+            Self::XavierUniform => uniform((6.0 / (fan_in + fan_out)).sqrt(), rng),
+            Self::XavierNormal => normal(len, (2.0 / (fan_in + fan_out)).sqrt(), rng),
+            Self::HeUniform => uniform((6.0 / fan_in).sqrt(), rng),
+            Self::HeNormal => normal(len, (2.0 / fan_in).sqrt(), rng),
+            Self::LeCunUniform => uniform((3.0 / fan_in).sqrt(), rng),
+            Self::LeCunNormal => normal(len, (1.0 / fan_in).sqrt(), rng),
+            Self::Orthogonal(gain) => orthogonal(len, fan_in as usize, gain, rng),
+        }
+    }
+}
+
+/// Box-Muller
+fn normal<R: Rng + ?Sized>(len: usize, std: f32, rng: &mut R) -> Vec<f32> {
+    (0..len).map(|_| {
+        let radius = (-2.0 * (1.0 - rng.random::<f32>()).ln()).sqrt();
+        radius * (std::f32::consts::TAU * rng.random::<f32>()).cos() * std
+    }).collect()
+}
+
+/// `len` values read as rows of `columns`, made orthonormal with Gram-Schmidt
+fn orthogonal<R: Rng + ?Sized>(len: usize, columns: usize, gain: f32, rng: &mut R) -> Vec<f32> {
+    let columns = columns.clamp(1, len.max(1));
+    let rows = len / columns;
+    let transposed = rows > columns;
+    let (count, size) = if transposed { (columns, rows) } else { (rows, columns) };
+
+    let mut vectors = normal(count * size, 1.0, rng);
+    for i in 0..count {
+        let (done, rest) = vectors.split_at_mut(i * size);
+        let vector = &mut rest[..size];
+        for other in done.chunks(size) {
+            let dot = vector.iter().zip(other).map(|(a, b)| a * b).sum::<f32>();
+            for (x, o) in vector.iter_mut().zip(other) {
+                *x -= dot * o;
+            }
+        }
+        let length = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        for x in vector.iter_mut() {
+            *x *= gain / length;
+        }
+    }
+
+    let mut result = vec![0.0; len];
+    for row in 0..rows {
+        for column in 0..columns {
+            result[row * columns + column] = if transposed { vectors[column * size + row] } else { vectors[row * size + column] };
+        }
+    }
+    result
+}
+
+#[test]
+fn orthogonal_is_orthonormal() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let rng = &mut StdRng::seed_from_u64(0);
+    for (rows, columns) in [(3, 5), (5, 3), (4, 4)] {
+        let values = orthogonal(rows * columns, columns, 1.0, rng);
+        let (count, size) = if rows <= columns { (rows, columns) } else { (columns, rows) };
+        let get = |vector: usize, i: usize| if rows <= columns { values[vector * columns + i] } else { values[i * columns + vector] };
+        for a in 0..count {
+            for b in 0..count {
+                let dot = (0..size).map(|i| get(a, i) * get(b, i)).sum::<f32>();
+                assert!((dot - (a == b) as u32 as f32).abs() < 1e-4);
+            }
+        }
+    }
+}
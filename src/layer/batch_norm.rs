@@ -0,0 +1,302 @@
+use std::sync::Arc;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::array::{Array1D, Array2D, Array3D, Tensor};
+
+use super::{chain::Leaf, ForwardContext, Layer, Mode, Parameter, ParameterMut, ParameterPath};
+
+/// Inputs batch norm can normalize, split into `N` groups that each get their own statistics
+pub trait FeatureGroups<const N: usize>: Tensor + Clone {
+    fn group(&self, index: usize) -> &[f32];
+    fn group_mut(&mut self, index: usize) -> &mut [f32];
+}
+impl<const N: usize> FeatureGroups<N> for Array1D<N> {
+    fn group(&self, index: usize) -> &[f32] {
+        std::slice::from_ref(&self.array[index])
+    }
+    fn group_mut(&mut self, index: usize) -> &mut [f32] {
+        std::slice::from_mut(&mut self.array[index])
+    }
+}
+/// a single channel feature map shares one set of statistics
+impl<const X: usize, const Y: usize> FeatureGroups<1> for Array2D<X, Y> {
+    fn group(&self, _index: usize) -> &[f32] {
+        self.array.as_flattened()
+    }
+    fn group_mut(&mut self, _index: usize) -> &mut [f32] {
+        self.array.as_flattened_mut()
+    }
+}
+impl<const C: usize, const X: usize, const Y: usize> FeatureGroups<C> for Array3D<C, X, Y> {
+    fn group(&self, index: usize) -> &[f32] {
+        self.array[index].as_flattened()
+    }
+    fn group_mut(&mut self, index: usize) -> &mut [f32] {
+        self.array[index].as_flattened_mut()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BatchStatistics<const N: usize> {
+    pub mean: Array1D<N>,
+    pub variance: Array1D<N>,
+    /// how many values each statistic was taken over, `0` if these are the running statistics
+    pub count: usize,
+}
+
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct BatchNorm<const N: usize> {
+    pub gamma: Array1D<N>,
+    pub beta: Array1D<N>,
+    pub running_mean: Array1D<N>,
+    pub running_variance: Array1D<N>,
+    pub momentum: f32,
+    pub epsilon: f32,
+    mode: Mode,
+}
+
+impl<const N: usize> Leaf for BatchNorm<N> {}
+
+impl<const N: usize> Default for BatchNorm<N> {
+    fn default() -> Self {
+        let mut gamma = Array1D::new();
+        gamma += 1.0;
+        let mut running_variance = Array1D::new();
+        running_variance += 1.0;
+        Self {
+            gamma,
+            beta: Array1D::new(),
+            running_mean: Array1D::new(),
+            running_variance,
+            momentum: 0.1,
+            epsilon: 1e-5,
+            mode: Mode::Training,
+        }
+    }
+}
+
+impl<const N: usize> BatchNorm<N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+    fn batch_statistics<T: FeatureGroups<N>>(inputs: &[T]) -> BatchStatistics<N> {
+        let mut statistics = BatchStatistics { mean: Array1D::new(), variance: Array1D::new(), count: 0 };
+        for index in 0..N {
+            let mut count = 0;
+            let mut sum = 0.0;
+            for input in inputs {
+                count += input.group(index).len();
+                sum += input.group(index).iter().sum::<f32>();
+            }
+            let mean = sum / count as f32;
+            let mut variance = 0.0;
+            for input in inputs {
+                variance += input.group(index).iter().map(|x| (x - mean).powi(2)).sum::<f32>();
+            }
+            statistics.mean[index] = mean;
+            statistics.variance[index] = variance / count as f32;
+            statistics.count = count;
+        }
+        statistics
+    }
+    fn running_statistics(&self) -> BatchStatistics<N> {
+        BatchStatistics { mean: self.running_mean.clone(), variance: self.running_variance.clone(), count: 0 }
+    }
+    fn normalize<T: FeatureGroups<N>>(&self, mut inputs: Vec<T>, statistics: Arc<BatchStatistics<N>>) -> (Vec<T>, Vec<(T, Arc<BatchStatistics<N>>)>) {
+        let mut forward_data = Vec::with_capacity(inputs.len());
+        for input in inputs.iter_mut() {
+            for index in 0..N {
+                let inverse_std = 1.0 / (statistics.variance[index] + self.epsilon).sqrt();
+                for x in input.group_mut(index) {
+                    *x = (*x - statistics.mean[index]) * inverse_std;
+                }
+            }
+            forward_data.push((input.clone(), statistics.clone()));
+            for index in 0..N {
+                for x in input.group_mut(index) {
+                    *x = *x * self.gamma[index] + self.beta[index];
+                }
+            }
+        }
+        (inputs, forward_data)
+    }
+}
+
+impl<const N: usize, T: FeatureGroups<N>> Layer<T> for BatchNorm<N> {
+    type Output = T;
+
+    /// the normalized input and the statistics it was normalized with
+    type ForwardData = (T, Arc<BatchStatistics<N>>);
+
+    /// gamma and beta
+    type Gradients = (Array1D<N>, Array1D<N>);
+
+    /// A single input is normalized with the running statistics, batch statistics need `forward_batch` in training mode
+    fn forward(&self, input: T) -> (Self::Output, Self::ForwardData) {
+        let (mut output, mut forward_data) = self.normalize(vec![input], Arc::new(self.running_statistics()));
+        (output.remove(0), forward_data.remove(0))
+    }
+
+    fn infer(&self, mut input: T) -> Self::Output {
+        for index in 0..N {
+            let scale = self.gamma[index] / (self.running_variance[index] + self.epsilon).sqrt();
+            for x in input.group_mut(index) {
+                *x = (*x - self.running_mean[index]) * scale + self.beta[index];
+            }
+        }
+        input
+    }
+
+    fn backward(&self, forward: Self::Output, forward_data: Self::ForwardData) -> (T, Self::Gradients) {
+        let (mut input, gradients) = self.backward_batch(vec![forward], vec![forward_data]);
+        (input.remove(0), gradients)
+    }
+
+    fn apply_gradients(&mut self, gradients: Self::Gradients, multiplier: f32) {
+        for (gamma, gradient) in self.gamma.iter_mut().zip(gradients.0.iter()) {
+            *gamma += *gradient * multiplier;
+        }
+        for (beta, gradient) in self.beta.iter_mut().zip(gradients.1.iter()) {
+            *beta += *gradient * multiplier;
+        }
+    }
+
+    fn visit_parameters(&self, visitor: &mut dyn FnMut(Parameter)) {
+        visitor(Parameter { path: ParameterPath::field("gamma"), decays: false, shape: &[N], values: self.gamma.as_slice() });
+        visitor(Parameter { path: ParameterPath::field("beta"), decays: false, shape: &[N], values: self.beta.as_slice() });
+    }
+
+    fn visit_parameters_mut(&mut self, visitor: &mut dyn FnMut(ParameterMut)) {
+        visitor(ParameterMut { path: ParameterPath::field("gamma"), decays: false, shape: &[N], values: self.gamma.as_mut_slice() });
+        visitor(ParameterMut { path: ParameterPath::field("beta"), decays: false, shape: &[N], values: self.beta.as_mut_slice() });
+    }
+
+    fn forward_batch_in(&self, inputs: Vec<T>, _context: ForwardContext) -> (Vec<Self::Output>, Vec<Self::ForwardData>) {
+        let statistics = match self.mode {
+            Mode::Training => Self::batch_statistics(&inputs),
+            Mode::Inference => self.running_statistics(),
+        };
+        self.normalize(inputs, Arc::new(statistics))
+    }
+
+    fn backward_batch_with(&self, mut forwards: Vec<Self::Output>, forward_data: Vec<Self::ForwardData>, _outputs: Option<Vec<Self::Output>>) -> (Vec<T>, Self::Gradients, Option<Vec<T>>) {
+        let mut gradients = (Array1D::new(), Array1D::new());
+        let Some(statistics) = forward_data.first().map(|x| x.1.clone()) else {
+            return (forwards, gradients, None);
+        };
+        for index in 0..N {
+            let inverse_std = 1.0 / (statistics.variance[index] + self.epsilon).sqrt();
+            let (mut sum, mut normalized_sum) = (0.0, 0.0);
+            for (forward, (normalized, _)) in forwards.iter().zip(&forward_data) {
+                for (gradient, normalized) in forward.group(index).iter().zip(normalized.group(index)) {
+                    sum += gradient;
+                    normalized_sum += gradient * normalized;
+                }
+            }
+            gradients.0[index] = normalized_sum;
+            gradients.1[index] = sum;
+
+            let scale = self.gamma[index] * inverse_std;
+            for (forward, (normalized, _)) in forwards.iter_mut().zip(&forward_data) {
+                for (gradient, normalized) in forward.group_mut(index).iter_mut().zip(normalized.group(index)) {
+                    if statistics.count == 0 {
+                        *gradient *= scale;
+                    } else {
+                        // the batch mean and variance depend on every input too
+                        *gradient = scale * (*gradient - (sum + normalized * normalized_sum) / statistics.count as f32);
+                    }
+                }
+            }
+        }
+        (forwards, gradients, None)
+    }
+
+    fn update_statistics(&mut self, forward_data: &Self::ForwardData) {
+        let statistics = &forward_data.1;
+        if statistics.count == 0 {
+            return;
+        }
+        // the running variance is unbiased
+        let correction = statistics.count as f32 / (statistics.count.max(2) - 1) as f32;
+        for index in 0..N {
+            self.running_mean[index] += self.momentum * (statistics.mean[index] - self.running_mean[index]);
+            self.running_variance[index] += self.momentum * (statistics.variance[index] * correction - self.running_variance[index]);
+        }
+    }
+
+    fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+
+    fn uses_batch_statistics(&self) -> bool {
+        self.mode == Mode::Training
+    }
+}
+
+#[test]
+fn batch_norm_gradients() {
+    let mut layer = BatchNorm::<2>::new();
+    layer.gamma = Array1D::from([1.5, 0.5].as_slice());
+    layer.beta = Array1D::from([0.2, -0.3].as_slice());
+    let inputs = (0..5).map(|x| {
+        let x = x as f32;
+        Array1D::from([x * 0.7 - 1.0, (x * 1.3).sin()].as_slice())
+    }).collect::<Vec<Array1D<2>>>();
+    let weights = (0..5).map(|x| {
+        let x = x as f32;
+        Array1D::from([x.cos(), 1.0 - x * 0.4].as_slice())
+    }).collect::<Vec<Array1D<2>>>();
+    let loss = |layer: &BatchNorm<2>, inputs: Vec<Array1D<2>>| {
+        let (outputs, _) = layer.forward_batch(inputs);
+        outputs.iter().zip(&weights).map(|(output, weight)| output.iter().zip(weight.iter()).map(|(a, b)| a * b).sum::<f32>()).sum::<f32>()
+    };
+
+    let (_, forward_data) = layer.forward_batch(inputs.clone());
+    let (input_gradients, (gamma_gradients, beta_gradients)) = layer.backward_batch(weights.clone(), forward_data);
+    for sample in 0..5 {
+        for index in 0..2 {
+            let mut above = inputs.clone();
+            above[sample][index] += 1e-2;
+            let mut below = inputs.clone();
+            below[sample][index] -= 1e-2;
+            let numeric = (loss(&layer, above) - loss(&layer, below)) / 2e-2;
+            assert!((numeric - input_gradients[sample][index]).abs() < 1e-2, "{numeric} {}", input_gradients[sample][index]);
+        }
+    }
+    for index in 0..2 {
+        for (parameter, analytic) in [(0, gamma_gradients[index]), (1, beta_gradients[index])] {
+            let nudged = |offset: f32| {
+                let mut layer = layer.clone();
+                let values = if parameter == 0 { &mut layer.gamma } else { &mut layer.beta };
+                values[index] += offset;
+                loss(&layer, inputs.clone())
+            };
+            let numeric = (nudged(1e-2) - nudged(-1e-2)) / 2e-2;
+            assert!((numeric - analytic).abs() < 1e-2, "{numeric} {analytic}");
+        }
+    }
+
+    // a single sample is normalized with the running statistics instead of being its own batch
+    let (single, _) = layer.forward(inputs[1].clone());
+    for (a, b) in single.iter().zip(layer.infer(inputs[1].clone()).iter()) {
+        assert!((a - b).abs() < 1e-6);
+    }
+    assert!(!single.iter().eq(layer.beta.iter()));
+
+    let (_, forward_data) = layer.forward_batch(inputs.clone());
+    layer.update_statistics(&forward_data[0]);
+    layer.set_mode(Mode::Inference);
+    let (outputs, _) = layer.forward_batch(inputs);
+    assert!((layer.running_mean[0] - 0.04).abs() < 1e-5);
+    assert_eq!(outputs.len(), 5);
+}
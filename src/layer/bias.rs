@@ -1,6 +1,6 @@
-use crate::array::Array2D;
+use crate::{array::{Array2D, Array3D}, initializer::Initializer};
 
-use super::Layer;
+use super::{chain::Leaf, Layer, Parameter, ParameterMut, ParameterPath};
 
 use rand::{rng, Rng};
 
@@ -13,12 +13,16 @@ use serde::{Deserialize, Serialize};
 pub struct BiasLayer<const X: usize, const Y: usize> {
     biases: Array2D<X, Y>,
 }
+
+impl<const X: usize, const Y: usize> Leaf for BiasLayer<X, Y> {}
 impl<const X: usize, const Y: usize> BiasLayer<X, Y> {
     pub fn new() -> Self {
         Self::default()
     }
     pub fn random() -> Self {
-        let mut rng = rng();
+        Self::random_with_rng(&mut rng())
+    }
+    pub fn random_with_rng(rng: &mut (impl Rng + ?Sized)) -> Self {
         let mut biases = Array2D::new();
         for x in 0..X {
             for y in 0..Y {
@@ -27,6 +31,14 @@ impl<const X: usize, const Y: usize> BiasLayer<X, Y> {
         }
         Self { biases }
     }
+    pub fn with_initializer(initializer: Initializer) -> Self {
+        Self::with_initializer_and_rng(initializer, &mut rng())
+    }
+    pub fn with_initializer_and_rng(initializer: Initializer, rng: &mut (impl Rng + ?Sized)) -> Self {
+        let mut x = Self::new();
+        initializer.initialize(&mut x.biases, 1, 1, rng);
+        x
+    }
 }
 impl<const X: usize, const Y: usize> Layer<Array2D<X, Y>> for BiasLayer<X, Y> {
     type Output = Array2D<X, Y>;
@@ -48,4 +60,51 @@ impl<const X: usize, const Y: usize> Layer<Array2D<X, Y>> for BiasLayer<X, Y> {
         gradients *= multiplier;
         self.biases += gradients
     }
-}
\ No newline at end of file
+
+    fn visit_parameters(&self, visitor: &mut dyn FnMut(Parameter)) {
+        visitor(Parameter { path: ParameterPath::field("biases"), decays: false, shape: &[Y, X], values: self.biases.as_flattened() });
+    }
+
+    fn visit_parameters_mut(&mut self, visitor: &mut dyn FnMut(ParameterMut)) {
+        visitor(ParameterMut { path: ParameterPath::field("biases"), decays: false, shape: &[Y, X], values: self.biases.as_flattened_mut() });
+    }
+}
+/// Adds the same biases to every channel
+impl<const C: usize, const X: usize, const Y: usize> Layer<Array3D<C, X, Y>> for BiasLayer<X, Y> {
+    type Output = Array3D<C, X, Y>;
+
+    type ForwardData = ();
+
+    type Gradients = Array2D<X, Y>;
+
+    fn forward(&self, mut input: Array3D<C, X, Y>) -> (Self::Output, Self::ForwardData) {
+        for channel in input.iter_mut() {
+            for (x, bias) in channel.as_flattened_mut().iter_mut().zip(self.biases.as_flattened()) {
+                *x += *bias;
+            }
+        }
+        (input, ())
+    }
+
+    fn backward(&self, forward: Self::Output, _forward_data: Self::ForwardData) -> (Array3D<C, X, Y>, Self::Gradients) {
+        let mut gradients = Array2D::new();
+        for channel in forward.iter() {
+            for (gradient, x) in gradients.as_flattened_mut().iter_mut().zip(channel.as_flattened()) {
+                *gradient += *x;
+            }
+        }
+        (forward, gradients)
+    }
+
+    fn apply_gradients(&mut self, gradients: Self::Gradients, multiplier: f32) {
+        Layer::<Array2D<X, Y>>::apply_gradients(self, gradients, multiplier);
+    }
+
+    fn visit_parameters(&self, visitor: &mut dyn FnMut(Parameter)) {
+        Layer::<Array2D<X, Y>>::visit_parameters(self, visitor);
+    }
+
+    fn visit_parameters_mut(&mut self, visitor: &mut dyn FnMut(ParameterMut)) {
+        Layer::<Array2D<X, Y>>::visit_parameters_mut(self, visitor);
+    }
+}
@@ -0,0 +1,214 @@
+use std::{marker::PhantomData, ops::{Add, Sub}};
+
+use typenum::{Cmp, Compare, Const, Diff, Equal, Greater, Less, Sum, ToUInt, Unsigned, U, U0, U1};
+
+use super::{Layer, LayerChain};
+
+/// Marks a layer that isn't a chain, so chains containing it can be indexed and split.
+/// Custom layers only need an empty `impl Leaf for MyLayer {}`.
+pub trait Leaf {}
+
+/// How many layers a chain holds, `()` doesn't count
+pub trait Length {
+    type Length: Unsigned;
+}
+impl Length for () {
+    type Length = U0;
+}
+impl<T: Leaf> Length for T {
+    type Length = U1;
+}
+impl<S: Length, N: Length, I> Length for LayerChain<S, N, I>
+where
+    S::Length: Add<N::Length>,
+    Sum<S::Length, N::Length>: Unsigned, {
+    type Length = Sum<S::Length, N::Length>;
+}
+
+/// The `K`th layer of a chain, counting from the input and skipping `()`
+pub trait ChainIndex<K> {
+    type Layer;
+    fn layer(&self) -> &Self::Layer;
+    fn layer_mut(&mut self) -> &mut Self::Layer;
+}
+impl<T: Leaf> ChainIndex<U0> for T {
+    type Layer = T;
+    fn layer(&self) -> &T {
+        self
+    }
+    fn layer_mut(&mut self) -> &mut T {
+        self
+    }
+}
+impl<S: Length, N, I, K: Cmp<S::Length>> ChainIndex<K> for LayerChain<S, N, I>
+where
+    Self: IndexBranch<K, Compare<K, S::Length>>, {
+    type Layer = <Self as IndexBranch<K, Compare<K, S::Length>>>::Layer;
+    fn layer(&self) -> &Self::Layer {
+        self.branch()
+    }
+    fn layer_mut(&mut self) -> &mut Self::Layer {
+        self.branch_mut()
+    }
+}
+
+/// `ChainIndex` picking `step` or `next` depending on how `K` compares to the length of `step`
+pub trait IndexBranch<K, Ordering> {
+    type Layer;
+    fn branch(&self) -> &Self::Layer;
+    fn branch_mut(&mut self) -> &mut Self::Layer;
+}
+impl<S: ChainIndex<K>, N, I, K> IndexBranch<K, Less> for LayerChain<S, N, I> {
+    type Layer = S::Layer;
+    fn branch(&self) -> &Self::Layer {
+        self.step.layer()
+    }
+    fn branch_mut(&mut self) -> &mut Self::Layer {
+        self.step.layer_mut()
+    }
+}
+impl<S: Length, N: ChainIndex<Diff<K, S::Length>>, I, K: Sub<S::Length>> IndexBranch<K, Equal> for LayerChain<S, N, I> {
+    type Layer = N::Layer;
+    fn branch(&self) -> &Self::Layer {
+        self.next.layer()
+    }
+    fn branch_mut(&mut self) -> &mut Self::Layer {
+        self.next.layer_mut()
+    }
+}
+impl<S: Length, N: ChainIndex<Diff<K, S::Length>>, I, K: Sub<S::Length>> IndexBranch<K, Greater> for LayerChain<S, N, I> {
+    type Layer = N::Layer;
+    fn branch(&self) -> &Self::Layer {
+        self.next.layer()
+    }
+    fn branch_mut(&mut self) -> &mut Self::Layer {
+        self.next.layer_mut()
+    }
+}
+
+/// Splits a chain taking `I` into its first `K` layers and the rest
+pub trait Split<K, I> {
+    type Prefix: Layer<I>;
+    type Suffix;
+    fn split(self) -> (Self::Prefix, Self::Suffix);
+}
+impl<I> Split<U0, I> for () {
+    type Prefix = ();
+    type Suffix = ();
+    fn split(self) -> ((), ()) {
+        ((), ())
+    }
+}
+impl<T: Leaf, I> Split<U0, I> for T {
+    type Prefix = ();
+    type Suffix = T;
+    fn split(self) -> ((), T) {
+        ((), self)
+    }
+}
+impl<T: Leaf + Layer<I>, I> Split<U1, I> for T {
+    type Prefix = T;
+    type Suffix = ();
+    fn split(self) -> (T, ()) {
+        (self, ())
+    }
+}
+impl<S: Length, N, I, K: Cmp<S::Length>> Split<K, I> for LayerChain<S, N, I>
+where
+    Self: SplitBranch<K, I, Compare<K, S::Length>>, {
+    type Prefix = <Self as SplitBranch<K, I, Compare<K, S::Length>>>::Prefix;
+    type Suffix = <Self as SplitBranch<K, I, Compare<K, S::Length>>>::Suffix;
+    fn split(self) -> (Self::Prefix, Self::Suffix) {
+        self.split_branch()
+    }
+}
+
+/// `Split` recursing into `step` or `next` depending on how `K` compares to the length of `step`
+pub trait SplitBranch<K, I, Ordering> {
+    type Prefix: Layer<I>;
+    type Suffix;
+    fn split_branch(self) -> (Self::Prefix, Self::Suffix);
+}
+impl<S, N, I, K> SplitBranch<K, I, Less> for LayerChain<S, N, I>
+where
+    S: Split<K, I>,
+    S::Suffix: Layer<<S::Prefix as Layer<I>>::Output>,
+    N: Layer<<S::Suffix as Layer<<S::Prefix as Layer<I>>::Output>>::Output>, {
+    type Prefix = S::Prefix;
+    type Suffix = LayerChain<S::Suffix, N, <S::Prefix as Layer<I>>::Output>;
+    fn split_branch(self) -> (Self::Prefix, Self::Suffix) {
+        let (prefix, suffix) = self.step.split();
+        (prefix, LayerChain { step: suffix, next: self.next, _marker: PhantomData })
+    }
+}
+impl<S: Layer<I>, N, I, K> SplitBranch<K, I, Equal> for LayerChain<S, N, I> {
+    type Prefix = S;
+    type Suffix = N;
+    fn split_branch(self) -> (S, N) {
+        (self.step, self.next)
+    }
+}
+impl<S, N, I, K> SplitBranch<K, I, Greater> for LayerChain<S, N, I>
+where
+    S: Layer<I> + Length,
+    K: Sub<S::Length>,
+    N: Split<Diff<K, S::Length>, S::Output>,
+    <N::Prefix as Layer<S::Output>>::Output: Sized, {
+    type Prefix = LayerChain<S, N::Prefix, I>;
+    type Suffix = N::Suffix;
+    fn split_branch(self) -> (Self::Prefix, Self::Suffix) {
+        let (prefix, suffix) = self.next.split();
+        (LayerChain { step: self.step, next: prefix, _marker: PhantomData }, suffix)
+    }
+}
+
+impl<S, N, I> LayerChain<S, N, I> {
+    /// The `K`th layer, counting from the input and skipping `()`
+    pub fn get<const K: usize>(&self) -> &<Self as ChainIndex<U<K>>>::Layer
+    where
+        Const<K>: ToUInt,
+        Self: ChainIndex<U<K>>, {
+        self.layer()
+    }
+    pub fn get_mut<const K: usize>(&mut self) -> &mut <Self as ChainIndex<U<K>>>::Layer
+    where
+        Const<K>: ToUInt,
+        Self: ChainIndex<U<K>>, {
+        self.layer_mut()
+    }
+    /// Splits into the first `K` layers and a chain of the rest, which takes the output of the first part
+    pub fn split<const K: usize>(self) -> (<Self as Split<U<K>, I>>::Prefix, <Self as Split<U<K>, I>>::Suffix)
+    where
+        Const<K>: ToUInt,
+        Self: Split<U<K>, I>, {
+        Split::split(self)
+    }
+}
+
+#[test]
+fn index_and_split_chains() {
+    use crate::{activation::{relu::Relu, sigmoid::Sigmoid}, array::Array1D, layer_chain, layer::dense::DenseLayer};
+
+    let mut pushed = LayerChain::<_, _, Array1D<3>>::new(DenseLayer::<3, 4>::random(), ())
+        .push(Sigmoid::new())
+        .push(DenseLayer::<4, 2>::random())
+        .push(Relu::new());
+    let nested: LayerChain<_, _, Array1D<3>> = layer_chain!(DenseLayer::<3, 4>::random(), Sigmoid::new(), DenseLayer::<4, 2>::random(), Relu::new());
+    assert_eq!(pushed.get::<2>().weights[1], pushed.step.next.weights[1]);
+    assert_eq!(nested.get::<2>().weights[1], nested.next.next.step.weights[1]);
+    pushed.get_mut::<0>().biases[0] = 5.0;
+    assert_eq!(pushed.step.step.step.step.biases[0], 5.0);
+
+    let input = Array1D::from([0.5, -1.0, 2.0].as_slice());
+    macro_rules! check_split {
+        ($chain:expr, $k:literal) => {
+            let (prefix, suffix) = $chain.clone().split::<$k>();
+            let (hidden, _) = prefix.forward(input.clone());
+            assert!(suffix.forward(hidden).0.iter().eq($chain.forward(input.clone()).0.iter()));
+        };
+    }
+    check_split!(pushed, 1);
+    check_split!(pushed, 3);
+    check_split!(nested, 1);
+    check_split!(nested, 3);
+}
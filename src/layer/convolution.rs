@@ -1,14 +1,30 @@
 use rand::{rng, Rng};
 
-use typenum::{Const, ToUInt, U};
-use std::ops::Rem;
+use typenum::{Add1, Const, Diff, Prod, Quot, Sub1, ToUInt, Unsigned, B1, U};
+use std::{marker::PhantomData, ops::{Add, Div, Mul, Rem, Sub}};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::array::Array2D;
+use crate::{array::{Array2D, Array3D, Tensor}, convolve::{correlate, Plane}, initializer::Initializer};
 
-use super::Layer;
+use super::{chain::Leaf, padding::{Padding, Zeros}, Layer, Parameter, ParameterMut, ParameterPath, PathSegment};
+
+/// How `Convolution` and `ChannelConvolution` compute their correlations, all of them give the same results up to rounding
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ConvolutionAlgorithm {
+    /// picks `Im2col` or `Fft` from the sizes involved, so the rounding can change with them
+    Auto,
+    /// loops over every kernel tap of every output
+    Direct,
+    /// multiplies the kernels with the matrix of input windows, rounds exactly like `Direct`
+    #[default]
+    Im2col,
+    /// multiplies in the frequency domain, fastest for large kernels
+    Fft,
+}
 
 #[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
@@ -18,9 +34,18 @@ where
     Const<N>: ToUInt,
     U<N>: Rem<U<2>, Output = U<1>>, {
     pub kernel: Array2D<N, N>,
-    rotated_kernel: Array2D<N, N>
+    rotated_kernel: Array2D<N, N>,
+    /// not archived by rkyv, so networks saved before it existed still load. Loaded layers use the default
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "rkyv", rkyv(with = rkyv::with::Skip))]
+    pub algorithm: ConvolutionAlgorithm,
 }
 
+impl<const N: usize> Leaf for Convolution<N>
+where
+    Const<N>: ToUInt,
+    U<N>: Rem<U<2>, Output = U<1>>, {}
+
 impl<const N: usize> Convolution<N>
 where
     Const<N>: ToUInt,
@@ -29,7 +54,9 @@ where
         Convolution::default()
     }
     pub fn random() -> Convolution<N> {
-        let mut rng = rng();
+        Self::random_with_rng(&mut rng())
+    }
+    pub fn random_with_rng(rng: &mut (impl Rng + ?Sized)) -> Convolution<N> {
         let mut array = Array2D::new();
         for y in 0..N {
             for x in 0..N {
@@ -37,10 +64,24 @@ where
             }
         }
 
-        let mut x = Self { kernel: array, rotated_kernel: Array2D::new() };
+        let mut x = Self { kernel: array, rotated_kernel: Array2D::new(), algorithm: ConvolutionAlgorithm::default() };
+        x.update_rotated_kernel();
+        x
+    }
+    pub fn with_initializer(initializer: Initializer) -> Convolution<N> {
+        Self::with_initializer_and_rng(initializer, &mut rng())
+    }
+    pub fn with_initializer_and_rng(initializer: Initializer, rng: &mut (impl Rng + ?Sized)) -> Convolution<N> {
+        let mut x = Self::new();
+        initializer.initialize(&mut x.kernel, N * N, N * N, rng);
         x.update_rotated_kernel();
         x
     }
+    /// builder style setter for `algorithm`
+    pub fn with_algorithm(mut self, algorithm: ConvolutionAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
     pub fn update_rotated_kernel(&mut self) {
         for y in 0..N {
             for x in 0..N {
@@ -48,52 +89,13 @@ where
             }
         }
     }
-    fn convolve<const X: usize, const Y: usize>(array: &Array2D<X, Y>, kernel: &Array2D<N, N>) -> Array2D<X, Y> {
-        let mut new = Array2D::new();
-        let kernel_offset = (N - 1)/2; // offset to move kernel center to pixel
-
-        for new_y in 0..Y {
-            for new_x in 0..X {
-                let mut value = 0.0;
-                for kernel_y in 0..N {
-                    for kernel_x in 0..N {
-                        value += kernel.array[kernel_y][kernel_x]
-                            * try_sample(
-                                array,
-                                // this can only fail if you have arrays with ridiculous sizes which no one can fit in memory so it's ok
-                                (new_x + kernel_x).wrapping_sub(kernel_offset),
-                                (new_y + kernel_y).wrapping_sub(kernel_offset),
-                            ).unwrap_or_default();
-                    }
-                }
-                new.array[new_y][new_x] = value;
-            }
-        }
-        new
+    /// adds the convolution of `array` with `kernel` onto `out`
+    fn convolve<const X: usize, const Y: usize>(algorithm: ConvolutionAlgorithm, array: &[[f32; X]; Y], kernel: &[[f32; N]; N], out: &mut [[f32; X]; Y]) {
+        correlate(algorithm, Plane::new(array), &[Plane::new(kernel)], (N - 1) / 2, &mut [out.as_flattened_mut()], X, Y);
     }
 
-    fn convolve_even_padded<const X: usize, const Y: usize>(array: &Array2D<X, Y>, kernel: &Array2D<X, Y>) -> Array2D<N, N> {
-        let mut new = Array2D::new();
-        let kernel_offset = (N - 1)/2; // offset to move kernel center to pixel
-
-        for new_y in 0..N {
-            for new_x in 0..N {
-                let mut value = 0.0;
-                for kernel_y in 0..Y {
-                    for kernel_x in 0..X {
-                    value += kernel.array[kernel_y][kernel_x]
-                        * try_sample(
-                            array,
-                            // this can only fail if you have arrays with ridiculous sizes which no one can fit in memory so it's ok
-                            (new_x + kernel_x).wrapping_sub(kernel_offset),
-                            (new_y + kernel_y).wrapping_sub(kernel_offset),
-                        ).unwrap_or_default();
-                    }
-                }
-                new.array[new_y][new_x] = value;
-            }
-        }
-        new
+    fn convolve_even_padded<const X: usize, const Y: usize>(algorithm: ConvolutionAlgorithm, array: &[[f32; X]; Y], kernel: &[[f32; X]; Y], out: &mut [[f32; N]; N]) {
+        correlate(algorithm, Plane::new(array), &[Plane::new(kernel)], (N - 1) / 2, &mut [out.as_flattened_mut()], N, N);
     }
 }
 
@@ -108,11 +110,23 @@ where
     type Gradients = Array2D<N, N>;
 
     fn forward(&self, input: Array2D<X, Y>) -> (Self::Output, Self::ForwardData) {
-        (Self::convolve(&input, &self.kernel), input)
+        let mut output = Array2D::new();
+        Self::convolve(self.algorithm, &input, &self.kernel, &mut output);
+        (output, input)
     }
 
     fn backward(&self, forward: Self::Output, forward_data: Self::ForwardData) -> (Array2D<X, Y>, Self::Gradients) {
-        (Self::convolve(&forward, &self.rotated_kernel), Self::convolve_even_padded(&forward_data, &forward))
+        let (input, gradients, _) = self.backward_with(forward, forward_data, None);
+        (input, gradients)
+    }
+
+    const KEEPS_INPUT: bool = true;
+    fn backward_with(&self, forward: Self::Output, forward_data: Self::ForwardData, _output: Option<Self::Output>) -> (Array2D<X, Y>, Self::Gradients, Option<Array2D<X, Y>>) {
+        let mut input_gradients = Array2D::new();
+        let mut kernel_gradients = Array2D::new();
+        Self::convolve(self.algorithm, &forward, &self.rotated_kernel, &mut input_gradients);
+        Self::convolve_even_padded(self.algorithm, &forward_data, &forward, &mut kernel_gradients);
+        (input_gradients, kernel_gradients, Some(forward_data))
     }
 
     fn apply_gradients(&mut self, mut gradients: Self::Gradients, multiplier: f32) {
@@ -120,12 +134,449 @@ where
         self.kernel += gradients;
         self.update_rotated_kernel();
     }
+
+    fn visit_parameters(&self, visitor: &mut dyn FnMut(Parameter)) {
+        visitor(Parameter { path: ParameterPath::field("kernel"), decays: true, shape: &[N, N], values: self.kernel.as_flattened() });
+    }
+
+    fn visit_parameters_mut(&mut self, visitor: &mut dyn FnMut(ParameterMut)) {
+        visitor(ParameterMut { path: ParameterPath::field("kernel"), decays: true, shape: &[N, N], values: self.kernel.as_flattened_mut() });
+        self.update_rotated_kernel();
+    }
+}
+
+/// Output size of a convolution over `X` pixels, `(X - trim * D * (N - 1) - 1) / S + 1`
+pub trait ConvolutionOutput<const X: usize, const N: usize, const S: usize, const D: usize> {
+    type Output;
+}
+
+impl<P: Padding, const X: usize, const N: usize, const S: usize, const D: usize> ConvolutionOutput<X, N, S, D> for P
+where
+    Const<X>: ToUInt,
+    Const<N>: ToUInt,
+    Const<S>: ToUInt,
+    Const<D>: ToUInt,
+    U<N>: Sub<B1>,
+    // span of the dilated kernel minus one
+    U<D>: Mul<Sub1<U<N>>>,
+    P::Trim: Mul<Prod<U<D>, Sub1<U<N>>>>,
+    U<X>: Sub<Prod<P::Trim, Prod<U<D>, Sub1<U<N>>>>>,
+    Diff<U<X>, Prod<P::Trim, Prod<U<D>, Sub1<U<N>>>>>: Sub<B1>,
+    Sub1<Diff<U<X>, Prod<P::Trim, Prod<U<D>, Sub1<U<N>>>>>>: Div<U<S>>,
+    Quot<Sub1<Diff<U<X>, Prod<P::Trim, Prod<U<D>, Sub1<U<N>>>>>>, U<S>>: Add<B1>, {
+    type Output = Add1<Quot<Sub1<Diff<U<X>, Prod<P::Trim, Prod<U<D>, Sub1<U<N>>>>>>, U<S>>>;
+}
+
+/// A convolution with stride `S`, dilation `D` and any [`Padding`], turning an `X`x`Y` input into an `A`x`B` output
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Clone, Default)]
+pub struct StridedConvolution<const N: usize, const A: usize, const B: usize, P = Zeros, const S: usize = 1, const D: usize = 1>
+where
+    Const<N>: ToUInt,
+    U<N>: Rem<U<2>, Output = U<1>>, {
+    pub kernel: Array2D<N, N>,
+    _padding_marker: PhantomData<P>,
 }
 
-/// samples with 0 padding
-fn try_sample<const X: usize, const Y: usize>(array: &Array2D<X, Y>, index_x: usize, index_y: usize) -> Option<f32> {
-    if index_x >= X || index_y >= Y {
-        return None;
+impl<const N: usize, const A: usize, const B: usize, P, const S: usize, const D: usize> Leaf for StridedConvolution<N, A, B, P, S, D>
+where
+    Const<N>: ToUInt,
+    U<N>: Rem<U<2>, Output = U<1>>, {}
+
+impl<const N: usize, const A: usize, const B: usize, P: Padding, const S: usize, const D: usize> StridedConvolution<N, A, B, P, S, D>
+where
+    Const<N>: ToUInt,
+    U<N>: Rem<U<2>, Output = U<1>>, {
+    pub fn new() -> Self {
+        Self { kernel: Array2D::new(), _padding_marker: PhantomData }
+    }
+    pub fn random() -> Self {
+        Self::random_with_rng(&mut rng())
+    }
+    pub fn random_with_rng(rng: &mut (impl Rng + ?Sized)) -> Self {
+        let mut kernel = Array2D::new();
+        for y in 0..N {
+            for x in 0..N {
+                kernel.array[y][x] = rng.random::<f32>() * 2.0 - 1.0;
+            }
+        }
+        Self { kernel, _padding_marker: PhantomData }
+    }
+    pub fn with_initializer(initializer: Initializer) -> Self {
+        Self::with_initializer_and_rng(initializer, &mut rng())
     }
-    Some(array.array[index_y][index_x])
-}
\ No newline at end of file
+    pub fn with_initializer_and_rng(initializer: Initializer, rng: &mut (impl Rng + ?Sized)) -> Self {
+        let mut x = Self::new();
+        initializer.initialize(&mut x.kernel, N * N, N * N, rng);
+        x
+    }
+    /// how far the kernel reaches outside of the input
+    fn padding() -> isize {
+        ((1 - P::Trim::USIZE) * D * (N - 1) / 2) as isize
+    }
+    /// calls `f` with every kernel position and the input position it lands on for output `(a, b)`
+    #[inline]
+    fn taps<const X: usize, const Y: usize>(a: usize, b: usize, mut f: impl FnMut(usize, usize, usize, usize)) {
+        let padding = Self::padding();
+        for kernel_y in 0..N {
+            let Some(y) = P::index((b * S + kernel_y * D) as isize - padding, Y) else {
+                continue;
+            };
+            for kernel_x in 0..N {
+                let Some(x) = P::index((a * S + kernel_x * D) as isize - padding, X) else {
+                    continue;
+                };
+                f(kernel_x, kernel_y, x, y);
+            }
+        }
+    }
+}
+
+impl<const X: usize, const Y: usize, const N: usize, const A: usize, const B: usize, P: Padding, const S: usize, const D: usize> Layer<Array2D<X, Y>> for StridedConvolution<N, A, B, P, S, D>
+where
+    Const<N>: ToUInt,
+    Const<A>: ToUInt,
+    Const<B>: ToUInt,
+    U<N>: Rem<U<2>, Output = U<1>>,
+    P: ConvolutionOutput<X, N, S, D, Output = U<A>> + ConvolutionOutput<Y, N, S, D, Output = U<B>>, {
+    type Output = Array2D<A, B>;
+
+    type ForwardData = Array2D<X, Y>;
+
+    type Gradients = Array2D<N, N>;
+
+    fn forward(&self, input: Array2D<X, Y>) -> (Self::Output, Self::ForwardData) {
+        let mut output = Array2D::new();
+        for b in 0..B {
+            for a in 0..A {
+                let mut value = 0.0;
+                Self::taps::<X, Y>(a, b, |kernel_x, kernel_y, x, y| {
+                    value += self.kernel.array[kernel_y][kernel_x] * input.array[y][x];
+                });
+                output.array[b][a] = value;
+            }
+        }
+        (output, input)
+    }
+
+    fn backward(&self, forward: Self::Output, forward_data: Self::ForwardData) -> (Array2D<X, Y>, Self::Gradients) {
+        let (input, gradients, _) = self.backward_with(forward, forward_data, None);
+        (input, gradients)
+    }
+
+    const KEEPS_INPUT: bool = true;
+    fn backward_with(&self, forward: Self::Output, forward_data: Self::ForwardData, _output: Option<Self::Output>) -> (Array2D<X, Y>, Self::Gradients, Option<Array2D<X, Y>>) {
+        let mut input_gradients = Array2D::<X, Y>::new();
+        let mut kernel_gradients = Array2D::new();
+        for b in 0..B {
+            for a in 0..A {
+                let gradient = forward.array[b][a];
+                // padded positions that map to the same input pixel all add onto it
+                Self::taps::<X, Y>(a, b, |kernel_x, kernel_y, x, y| {
+                    input_gradients.array[y][x] += gradient * self.kernel.array[kernel_y][kernel_x];
+                    kernel_gradients.array[kernel_y][kernel_x] += gradient * forward_data.array[y][x];
+                });
+            }
+        }
+        (input_gradients, kernel_gradients, Some(forward_data))
+    }
+
+    fn apply_gradients(&mut self, mut gradients: Self::Gradients, multiplier: f32) {
+        gradients *= multiplier;
+        self.kernel += gradients;
+    }
+
+    fn visit_parameters(&self, visitor: &mut dyn FnMut(Parameter)) {
+        visitor(Parameter { path: ParameterPath::field("kernel"), decays: true, shape: &[N, N], values: self.kernel.as_flattened() });
+    }
+
+    fn visit_parameters_mut(&mut self, visitor: &mut dyn FnMut(ParameterMut)) {
+        visitor(ParameterMut { path: ParameterPath::field("kernel"), decays: true, shape: &[N, N], values: self.kernel.as_flattened_mut() });
+    }
+}
+
+/// A convolution from `IN_C` to `OUT_C` channels, every output channel has one `N`x`N` kernel per input channel
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Clone)]
+pub struct ChannelConvolution<const N: usize, const IN_C: usize, const OUT_C: usize>
+where
+    Const<N>: ToUInt,
+    U<N>: Rem<U<2>, Output = U<1>>, {
+    #[cfg_attr(feature = "serde", serde(with = "serde_with::As::<[serde_with::Same; OUT_C]>"))]
+    pub kernels: [Array3D<IN_C, N, N>; OUT_C],
+    #[cfg_attr(feature = "serde", serde(with = "serde_with::As::<[serde_with::Same; OUT_C]>"))]
+    rotated_kernels: [Array3D<IN_C, N, N>; OUT_C],
+    /// not archived by rkyv, so networks saved before it existed still load. Loaded layers use the default
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "rkyv", rkyv(with = rkyv::with::Skip))]
+    pub algorithm: ConvolutionAlgorithm,
+}
+
+impl<const N: usize, const IN_C: usize, const OUT_C: usize> Leaf for ChannelConvolution<N, IN_C, OUT_C>
+where
+    Const<N>: ToUInt,
+    U<N>: Rem<U<2>, Output = U<1>>, {}
+
+impl<const N: usize, const IN_C: usize, const OUT_C: usize> Default for ChannelConvolution<N, IN_C, OUT_C>
+where
+    Const<N>: ToUInt,
+    U<N>: Rem<U<2>, Output = U<1>>, {
+    fn default() -> Self {
+        Self { kernels: Tensor::zeroed(), rotated_kernels: Tensor::zeroed(), algorithm: ConvolutionAlgorithm::default() }
+    }
+}
+
+impl<const N: usize, const IN_C: usize, const OUT_C: usize> ChannelConvolution<N, IN_C, OUT_C>
+where
+    Const<N>: ToUInt,
+    U<N>: Rem<U<2>, Output = U<1>>, {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn random() -> Self {
+        Self::random_with_rng(&mut rng())
+    }
+    pub fn random_with_rng(rng: &mut (impl Rng + ?Sized)) -> Self {
+        let mut kernels: [Array3D<IN_C, N, N>; OUT_C] = Tensor::zeroed();
+        for kernel in kernels.iter_mut() {
+            for weight in kernel.array.as_flattened_mut().as_flattened_mut() {
+                *weight = rng.random::<f32>() * 2.0 - 1.0;
+            }
+        }
+
+        let mut x = Self { kernels, rotated_kernels: Tensor::zeroed(), algorithm: ConvolutionAlgorithm::default() };
+        x.update_rotated_kernels();
+        x
+    }
+    pub fn with_initializer(initializer: Initializer) -> Self {
+        Self::with_initializer_and_rng(initializer, &mut rng())
+    }
+    pub fn with_initializer_and_rng(initializer: Initializer, rng: &mut (impl Rng + ?Sized)) -> Self {
+        let mut x = Self::new();
+        initializer.initialize(&mut x.kernels, IN_C * N * N, OUT_C * N * N, rng);
+        x.update_rotated_kernels();
+        x
+    }
+    /// builder style setter for `algorithm`
+    pub fn with_algorithm(mut self, algorithm: ConvolutionAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+    pub fn update_rotated_kernels(&mut self) {
+        for (kernel, rotated) in self.kernels.iter().zip(self.rotated_kernels.iter_mut()) {
+            for (kernel, rotated) in kernel.iter().zip(rotated.iter_mut()) {
+                for y in 0..N {
+                    for x in 0..N {
+                        rotated[y][x] = kernel[N - 1 - y][N - 1 - x];
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<const X: usize, const Y: usize, const N: usize, const IN_C: usize, const OUT_C: usize> Layer<Array3D<IN_C, X, Y>> for ChannelConvolution<N, IN_C, OUT_C>
+where
+    Const<N>: ToUInt,
+    U<N>: Rem<U<2>, Output = U<1>>, {
+    type Output = Array3D<OUT_C, X, Y>;
+
+    type ForwardData = Array3D<IN_C, X, Y>;
+
+    type Gradients = [Array3D<IN_C, N, N>; OUT_C];
+
+    fn forward(&self, input: Array3D<IN_C, X, Y>) -> (Self::Output, Self::ForwardData) {
+        let mut output = Array3D::<OUT_C, X, Y>::new();
+        // every output channel at once, so an input channel is only gathered or transformed once
+        for (channel, input) in input.iter().enumerate() {
+            let kernels = self.kernels.iter().map(|kernel| Plane::new(&kernel[channel])).collect::<Vec<_>>();
+            let mut outputs = output.iter_mut().map(|output| output.as_flattened_mut()).collect::<Vec<_>>();
+            correlate(self.algorithm, Plane::new(input), &kernels, (N - 1) / 2, &mut outputs, X, Y);
+        }
+        (output, input)
+    }
+
+    fn backward(&self, forward: Self::Output, forward_data: Self::ForwardData) -> (Array3D<IN_C, X, Y>, Self::Gradients) {
+        let (input, gradients, _) = self.backward_with(forward, forward_data, None);
+        (input, gradients)
+    }
+
+    const KEEPS_INPUT: bool = true;
+    fn backward_with(&self, forward: Self::Output, forward_data: Self::ForwardData, _output: Option<Self::Output>) -> (Array3D<IN_C, X, Y>, Self::Gradients, Option<Array3D<IN_C, X, Y>>) {
+        let mut input_gradients = Array3D::new();
+        let mut kernel_gradients: Self::Gradients = Tensor::zeroed();
+        for (forward, rotated) in forward.iter().zip(self.rotated_kernels.iter()) {
+            let kernels = rotated.iter().map(Plane::new).collect::<Vec<_>>();
+            let mut outputs = input_gradients.iter_mut().map(|gradients| gradients.as_flattened_mut()).collect::<Vec<_>>();
+            correlate(self.algorithm, Plane::new(forward), &kernels, (N - 1) / 2, &mut outputs, X, Y);
+        }
+        let forwards = forward.iter().map(Plane::new).collect::<Vec<_>>();
+        for (channel, input) in forward_data.iter().enumerate() {
+            let mut outputs = kernel_gradients.iter_mut().map(|gradients| gradients[channel].as_flattened_mut()).collect::<Vec<_>>();
+            correlate(self.algorithm, Plane::new(input), &forwards, (N - 1) / 2, &mut outputs, N, N);
+        }
+        (input_gradients, kernel_gradients, Some(forward_data))
+    }
+
+    fn apply_gradients(&mut self, gradients: Self::Gradients, multiplier: f32) {
+        for (kernel, mut gradients) in self.kernels.iter_mut().zip(gradients) {
+            gradients *= multiplier;
+            *kernel += gradients;
+        }
+        self.update_rotated_kernels();
+    }
+
+    /// every output channel's kernel is visited on its own as `kernels.{channel}`
+    fn visit_parameters(&self, visitor: &mut dyn FnMut(Parameter)) {
+        for (i, kernel) in self.kernels.iter().enumerate() {
+            let index = ParameterPath::new(PathSegment::Index(i), None);
+            visitor(Parameter { path: ParameterPath::new(PathSegment::Field("kernels"), Some(&index)), decays: true, shape: &[IN_C, N, N], values: kernel.as_flattened().as_flattened() });
+        }
+    }
+
+    fn visit_parameters_mut(&mut self, visitor: &mut dyn FnMut(ParameterMut)) {
+        for (i, kernel) in self.kernels.iter_mut().enumerate() {
+            let index = ParameterPath::new(PathSegment::Index(i), None);
+            visitor(ParameterMut { path: ParameterPath::new(PathSegment::Field("kernels"), Some(&index)), decays: true, shape: &[IN_C, N, N], values: kernel.as_flattened_mut().as_flattened_mut() });
+        }
+        self.update_rotated_kernels();
+    }
+}
+
+#[test]
+fn single_channel_matches_convolution() {
+    let convolution = Convolution::<3>::random();
+    let mut channel_convolution = ChannelConvolution::<3, 1, 1>::new();
+    *channel_convolution.kernels[0].array = [*convolution.kernel.array];
+    channel_convolution.update_rotated_kernels();
+
+    let mut input = Array2D::<5, 4>::new();
+    for (i, x) in input.iter_mut().flatten().enumerate() {
+        *x = (i as f32).sin();
+    }
+    let channel_input = Array3D::from(Box::new([*input.array]));
+
+    let (output, forward_data) = convolution.forward(input);
+    let (channel_output, channel_forward_data) = channel_convolution.forward(channel_input);
+    assert_eq!(*output.array, channel_output.array[0]);
+
+    let (input_gradients, kernel_gradients) = convolution.backward(output, forward_data);
+    let (channel_input_gradients, channel_kernel_gradients) = channel_convolution.backward(channel_output, channel_forward_data);
+    assert_eq!(*input_gradients.array, channel_input_gradients.array[0]);
+    assert_eq!(*kernel_gradients.array, channel_kernel_gradients[0].array[0]);
+}
+
+#[test]
+fn strided_convolution_gradients() {
+    use super::padding::{Circular, Reflect, Replicate, Valid};
+
+    fn check<const A: usize, const B: usize, P: Padding + Clone, const S: usize, const D: usize>()
+    where
+        StridedConvolution<3, A, B, P, S, D>: Layer<Array2D<7, 6>, Output = Array2D<A, B>, ForwardData = Array2D<7, 6>, Gradients = Array2D<3, 3>>, {
+        let layer = StridedConvolution::<3, A, B, P, S, D>::random();
+        let mut input = Array2D::<7, 6>::new();
+        for (i, x) in input.iter_mut().flatten().enumerate() {
+            *x = (i as f32 * 0.7).sin();
+        }
+        // loss is the sum of the outputs weighted by `weights`
+        let mut weights = Array2D::<A, B>::new();
+        for (i, x) in weights.iter_mut().flatten().enumerate() {
+            *x = (i as f32 * 1.3).cos();
+        }
+        let loss = |layer: &StridedConvolution<3, A, B, P, S, D>, input: Array2D<7, 6>| -> f32 {
+            layer.forward(input).0.iter().flatten().zip(weights.iter().flatten()).map(|(x, w)| x * w).sum()
+        };
+        let (input_gradients, kernel_gradients) = layer.backward(weights.clone(), input.clone());
+
+        let epsilon = 1e-2;
+        for y in 0..6 {
+            for x in 0..7 {
+                let (mut plus, mut minus) = (input.clone(), input.clone());
+                plus[y][x] += epsilon;
+                minus[y][x] -= epsilon;
+                let numeric = (loss(&layer, plus) - loss(&layer, minus)) / (2.0 * epsilon);
+                assert!((numeric - input_gradients[y][x]).abs() < 1e-2, "input ({x}, {y}): {numeric} != {}", input_gradients[y][x]);
+            }
+        }
+        for y in 0..3 {
+            for x in 0..3 {
+                let (mut plus, mut minus) = (layer.clone(), layer.clone());
+                plus.kernel[y][x] += epsilon;
+                minus.kernel[y][x] -= epsilon;
+                let numeric = (loss(&plus, input.clone()) - loss(&minus, input.clone())) / (2.0 * epsilon);
+                assert!((numeric - kernel_gradients[y][x]).abs() < 1e-2, "kernel ({x}, {y}): {numeric} != {}", kernel_gradients[y][x]);
+            }
+        }
+    }
+
+    check::<5, 4, Valid, 1, 1>();
+    check::<3, 2, Valid, 2, 1>();
+    check::<3, 2, Valid, 1, 2>();
+    check::<2, 1, Valid, 2, 2>();
+    check::<7, 6, Zeros, 1, 1>();
+    check::<4, 3, Zeros, 2, 2>();
+    check::<7, 6, Reflect, 1, 2>();
+    check::<4, 3, Reflect, 2, 1>();
+    check::<7, 6, Replicate, 1, 2>();
+    check::<4, 3, Replicate, 2, 2>();
+    check::<7, 6, Circular, 1, 2>();
+    check::<3, 2, Circular, 3, 1>();
+}
+
+#[test]
+fn algorithms_match_direct() {
+    fn check<const N: usize, const X: usize, const Y: usize>()
+    where
+        Const<N>: ToUInt,
+        U<N>: Rem<U<2>, Output = U<1>>, {
+        let direct = ChannelConvolution::<N, 2, 3>::random().with_algorithm(ConvolutionAlgorithm::Direct);
+        let mut input = Array3D::<2, X, Y>::new();
+        for (i, x) in input.iter_mut().flatten().flatten().enumerate() {
+            *x = (i as f32 * 0.3).sin();
+        }
+        let (output, forward_data) = direct.forward(input.clone());
+        let (input_gradients, kernel_gradients) = direct.backward(output.clone(), forward_data);
+
+        for algorithm in [ConvolutionAlgorithm::Im2col, ConvolutionAlgorithm::Fft, ConvolutionAlgorithm::Auto] {
+            let layer = direct.clone().with_algorithm(algorithm);
+            let (other_output, forward_data) = layer.forward(input.clone());
+            let (other_input_gradients, other_kernel_gradients) = layer.backward(other_output.clone(), forward_data);
+            let pairs = output.as_flattened().as_flattened().iter().zip(other_output.as_flattened().as_flattened())
+                .chain(input_gradients.as_flattened().as_flattened().iter().zip(other_input_gradients.as_flattened().as_flattened()))
+                .chain(kernel_gradients.iter().zip(&other_kernel_gradients).flat_map(|(a, b)| a.as_flattened().as_flattened().iter().zip(b.as_flattened().as_flattened())));
+            for (a, b) in pairs {
+                if algorithm == ConvolutionAlgorithm::Im2col {
+                    assert_eq!(a, b, "N={N} {X}x{Y}");
+                } else {
+                    assert!((a - b).abs() <= 1e-3 * (1.0 + a.abs()), "{algorithm:?} N={N} {X}x{Y}: {a} != {b}");
+                }
+            }
+        }
+    }
+    check::<1, 4, 3>();
+    check::<3, 5, 4>();
+    check::<5, 9, 17>();
+    check::<7, 3, 2>();
+    check::<15, 20, 18>();
+}
+
+#[cfg(feature = "rkyv")]
+#[test]
+fn archives_like_before_algorithm() {
+    // the layout `Convolution` was saved with before it had an `algorithm`
+    #[derive(rkyv::Archive, rkyv::Serialize)]
+    struct Saved {
+        kernel: Array2D<3, 3>,
+        rotated_kernel: Array2D<3, 3>,
+    }
+    let convolution = Convolution::<3>::random().with_algorithm(ConvolutionAlgorithm::Fft);
+    let saved = Saved { kernel: convolution.kernel.clone(), rotated_kernel: convolution.rotated_kernel.clone() };
+    let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&saved).unwrap();
+    assert_eq!(rkyv::to_bytes::<rkyv::rancor::Error>(&convolution).unwrap().as_slice(), bytes.as_slice());
+
+    let loaded = rkyv::from_bytes::<Convolution<3>, rkyv::rancor::Error>(&bytes).unwrap();
+    assert!(loaded.kernel.iter().eq(convolution.kernel.iter()));
+    assert_eq!(loaded.algorithm, ConvolutionAlgorithm::default());
+}
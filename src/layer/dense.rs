@@ -3,9 +3,9 @@ use rand::{rng, Rng};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::array::{Array1D, Array2D};
+use crate::{array::{Array1D, Array2D}, gemm::{gemm, Matrix}, initializer::Initializer};
 
-use super::Layer;
+use super::{chain::Leaf, ForwardContext, Layer, Parameter, ParameterMut, ParameterPath};
 
 #[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -14,23 +14,29 @@ pub struct DenseLayer<const I: usize, const O: usize> {
     pub weights: Array2D<I, O>,
     pub biases: Array1D<O>,
 }
+
+impl<const I: usize, const O: usize> Leaf for DenseLayer<I, O> {}
 impl<const I: usize, const O: usize> Layer<Array1D<I>> for DenseLayer<I, O> {
     type Output = Array1D<O>;
     type ForwardData = Array1D<I>;
     type Gradients = (Array2D<I, O>, Array1D<O>);
 
     fn forward(&self, input: Array1D<I>) -> (Self::Output, Self::ForwardData) {
-        let forward_data = input.clone();
-        let mut output = self.biases.clone();
-        for (i, node) in output.iter_mut().enumerate() {
-            for (j, input) in input.iter().enumerate() {
-                *node += self.weights[i][j] * input;
-            }
-        }
-        (output, forward_data)
+        let output = self.infer_ref(&input);
+        (output, input)
+    }
+
+    fn infer(&self, input: Array1D<I>) -> Self::Output {
+        self.infer_ref(&input)
     }
 
     fn backward(&self, forward: Self::Output, forward_data: Self::ForwardData) -> (Array1D<I>, Self::Gradients) {
+        let (input, gradients, _) = self.backward_with(forward, forward_data, None);
+        (input, gradients)
+    }
+
+    const KEEPS_INPUT: bool = true;
+    fn backward_with(&self, forward: Self::Output, forward_data: Self::ForwardData, _output: Option<Self::Output>) -> (Array1D<I>, Self::Gradients, Option<Array1D<I>>) {
         let mut gradients = Self::Gradients::default();
         for (i, bias) in gradients.1.iter_mut().enumerate() {
             *bias += forward[i];
@@ -42,7 +48,7 @@ impl<const I: usize, const O: usize> Layer<Array1D<I>> for DenseLayer<I, O> {
                 output[i] += self.weights[o][i] * forward[o];
             }
         }
-        (output, gradients)
+        (output, gradients, Some(forward_data))
     }
 
     fn apply_gradients(&mut self, gradients: Self::Gradients, multiplier: f32) {
@@ -55,6 +61,47 @@ impl<const I: usize, const O: usize> Layer<Array1D<I>> for DenseLayer<I, O> {
             }
         }
     }
+
+    /// Runs the whole batch as one matrix product, the result is identical to calling `forward` per sample
+    fn forward_batch_in(&self, inputs: Vec<Array1D<I>>, _context: ForwardContext) -> (Vec<Self::Output>, Vec<Self::ForwardData>) {
+        let batch = inputs.len();
+        let input_matrix = inputs.iter().flat_map(|input| input.iter().copied()).collect::<Vec<_>>();
+        let mut output_matrix = Vec::with_capacity(batch * O);
+        for _ in 0..batch {
+            output_matrix.extend_from_slice(self.biases.as_slice());
+        }
+        gemm(batch, O, I, Matrix::rows(&input_matrix, I), Matrix::columns(self.weights.as_flattened(), I), &mut output_matrix);
+        let outputs = (0..batch).map(|sample| Array1D::from(&output_matrix[sample * O..(sample + 1) * O])).collect();
+        (outputs, inputs)
+    }
+
+    fn backward_batch_with(&self, forwards: Vec<Self::Output>, forward_data: Vec<Self::ForwardData>, _outputs: Option<Vec<Self::Output>>) -> (Vec<Array1D<I>>, Self::Gradients, Option<Vec<Array1D<I>>>) {
+        let batch = forwards.len();
+        let mut gradients = Self::Gradients::default();
+        for forward in &forwards {
+            for (bias, gradient) in gradients.1.iter_mut().zip(forward.iter()) {
+                *bias += gradient;
+            }
+        }
+        let forward_matrix = forwards.iter().flat_map(|forward| forward.iter().copied()).collect::<Vec<_>>();
+        let input_matrix = forward_data.iter().flat_map(|input| input.iter().copied()).collect::<Vec<_>>();
+        // weight gradients are forwardsᵀ * inputs, input gradients are forwards * weights
+        gemm(O, I, batch, Matrix::columns(&forward_matrix, O), Matrix::rows(&input_matrix, I), gradients.0.as_flattened_mut());
+        let mut input_gradients = vec![0.0; batch * I];
+        gemm(batch, I, O, Matrix::rows(&forward_matrix, O), Matrix::rows(self.weights.as_flattened(), I), &mut input_gradients);
+        let input_gradients = (0..batch).map(|sample| Array1D::from(&input_gradients[sample * I..(sample + 1) * I])).collect();
+        (input_gradients, gradients, Some(forward_data))
+    }
+
+    fn visit_parameters(&self, visitor: &mut dyn FnMut(Parameter)) {
+        visitor(Parameter { path: ParameterPath::field("weights"), decays: true, shape: &[O, I], values: self.weights.as_flattened() });
+        visitor(Parameter { path: ParameterPath::field("biases"), decays: false, shape: &[O], values: self.biases.as_slice() });
+    }
+
+    fn visit_parameters_mut(&mut self, visitor: &mut dyn FnMut(ParameterMut)) {
+        visitor(ParameterMut { path: ParameterPath::field("weights"), decays: true, shape: &[O, I], values: self.weights.as_flattened_mut() });
+        visitor(ParameterMut { path: ParameterPath::field("biases"), decays: false, shape: &[O], values: self.biases.as_mut_slice() });
+    }
 }
 
 impl<const I: usize, const O: usize> DenseLayer<I, O> {
@@ -63,7 +110,9 @@ impl<const I: usize, const O: usize> DenseLayer<I, O> {
     }
 
     pub fn random() -> Self {
-        let mut rng = rng();
+        Self::random_with_rng(&mut rng())
+    }
+    pub fn random_with_rng(rng: &mut (impl Rng + ?Sized)) -> Self {
         let mut biases = Array1D::new();
         for bias in biases.iter_mut() {
             *bias = rng.random::<f32>() * 2.0 - 1.0;
@@ -79,4 +128,50 @@ impl<const I: usize, const O: usize> DenseLayer<I, O> {
             biases,
         }
     }
+    /// initializes the weights, biases start at zero
+    pub fn with_initializer(initializer: Initializer) -> Self {
+        Self::with_initializer_and_rng(initializer, &mut rng())
+    }
+    pub fn with_initializer_and_rng(initializer: Initializer, rng: &mut (impl Rng + ?Sized)) -> Self {
+        let mut layer = Self::new();
+        initializer.initialize(&mut layer.weights, I, O, rng);
+        layer
+    }
+    fn infer_ref(&self, input: &Array1D<I>) -> Array1D<O> {
+        let mut output = self.biases.clone();
+        for (i, node) in output.iter_mut().enumerate() {
+            for (j, input) in input.iter().enumerate() {
+                *node += self.weights[i][j] * input;
+            }
+        }
+        output
+    }
+}
+
+#[test]
+fn batched_matches_per_sample() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(19);
+    // wider than one cache block and a batch that isn't a multiple of the row blocking
+    let layer = DenseLayer::<300, 7>::random_with_rng(&mut rng);
+    let inputs = (0..6).map(|_| Array1D::from((0..300).map(|_| rng.random::<f32>()).collect::<Vec<_>>().as_slice())).collect::<Vec<_>>();
+    let (outputs, forward_data) = layer.forward_batch(inputs.clone());
+    for (input, output) in inputs.iter().zip(&outputs) {
+        assert_eq!(layer.forward(input.clone()).0.as_slice(), output.as_slice());
+    }
+
+    let mut gradients = layer.zeroed_gradients();
+    let mut input_gradients = vec![];
+    for (output, input) in outputs.iter().zip(inputs) {
+        let (input_gradient, sample) = layer.backward(output.clone(), input);
+        layer.accumulate_gradients(&mut gradients, sample, 1.0);
+        input_gradients.push(input_gradient);
+    }
+    let (batched_inputs, batched) = layer.backward_batch(outputs, forward_data);
+    for (a, b) in input_gradients.iter().zip(&batched_inputs) {
+        assert_eq!(a.as_slice(), b.as_slice());
+    }
+    assert_eq!(gradients.0.as_flattened(), batched.0.as_flattened());
+    assert_eq!(gradients.1.as_slice(), batched.1.as_slice());
 }
@@ -0,0 +1,157 @@
+use rand::{rng, rngs::StdRng, Rng, SeedableRng};
+use typenum::{Cmp, Const, Less, ToUInt, U};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::array::Tensor;
+
+use super::{chain::Leaf, ForwardContext, Layer, Mode, Parameter, ParameterMut};
+
+/// Inverted dropout, zeroes `P` percent of its inputs while training and scales the rest up to keep the expected value.
+/// The mask of a sample only depends on the seed, the number of batches learned so far and the sample's index in its batch,
+/// so training is reproducible however the batches are split across threads.
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Dropout<const P: usize>
+where
+    Const<P>: ToUInt,
+    U<P>: Cmp<U<100>, Output = Less> {
+    mode: Mode,
+    seed: u64,
+    /// batches learned so far, advanced by `end_batch`
+    step: u64,
+}
+
+impl<const P: usize> Leaf for Dropout<P>
+where
+    Const<P>: ToUInt,
+    U<P>: Cmp<U<100>, Output = Less>, {}
+
+impl<const P: usize> Dropout<P>
+where
+    Const<P>: ToUInt,
+    U<P>: Cmp<U<100>, Output = Less> {
+    /// with a random seed
+    pub fn new() -> Self {
+        Self::seeded(rng().random())
+    }
+    pub fn seeded(seed: u64) -> Self {
+        Self { mode: Mode::Training, seed, step: 0 }
+    }
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+    fn sample_rng(&self, sample: usize) -> StdRng {
+        let mut seed = [0; 32];
+        for (bytes, value) in seed.chunks_mut(8).zip([self.seed, self.step, sample as u64]) {
+            bytes.copy_from_slice(&value.to_le_bytes());
+        }
+        StdRng::from_seed(seed)
+    }
+}
+
+impl<const P: usize, T: Tensor> Layer<T> for Dropout<P>
+where
+    Const<P>: ToUInt,
+    U<P>: Cmp<U<100>, Output = Less> {
+    type Output = T;
+
+    /// the scale each input was multiplied by, `None` in inference mode
+    type ForwardData = Option<T>;
+
+    type Gradients = ();
+
+    /// Drops units as if `input` was the first sample of a batch
+    fn forward(&self, input: T) -> (Self::Output, Self::ForwardData) {
+        self.forward_in(input, ForwardContext::default())
+    }
+
+    fn forward_in(&self, mut input: T, context: ForwardContext) -> (Self::Output, Self::ForwardData) {
+        if self.mode == Mode::Inference || P == 0 {
+            return (input, None);
+        }
+        let keep = 1.0 - P as f32 / 100.0;
+        let mut mask = T::zeroed();
+        let mut rng = self.sample_rng(context.sample);
+        for (input, mask) in input.slices_mut().zip(mask.slices_mut()) {
+            for (x, scale) in input.iter_mut().zip(mask.iter_mut()) {
+                if rng.random::<f32>() < keep {
+                    *scale = 1.0 / keep;
+                }
+                *x *= *scale;
+            }
+        }
+        (input, Some(mask))
+    }
+
+    fn backward(&self, mut forward: Self::Output, forward_data: Self::ForwardData) -> (T, Self::Gradients) {
+        if let Some(mask) = forward_data {
+            for (forward, mask) in forward.slices_mut().zip(mask.slices()) {
+                for (x, scale) in forward.iter_mut().zip(mask) {
+                    *x *= *scale;
+                }
+            }
+        }
+        (forward, ())
+    }
+
+    fn apply_gradients(&mut self, _gradients: Self::Gradients, _multiplier: f32) {}
+
+    fn visit_parameters(&self, _visitor: &mut dyn FnMut(Parameter)) {}
+
+    fn visit_parameters_mut(&mut self, _visitor: &mut dyn FnMut(ParameterMut)) {}
+
+    /// Moves on to the masks of the next batch
+    fn end_batch(&mut self) {
+        self.step += 1;
+    }
+
+    fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+}
+
+#[test]
+fn dropout_routes_gradients_through_kept_units() {
+    use crate::array::Array1D;
+
+    let mut layer = Dropout::<25>::new();
+    let input = Array1D::<1000>::from([1.0; 1000].as_slice());
+    let (output, mask) = layer.forward(input.clone());
+    let kept = output.iter().filter(|x| **x != 0.0).count();
+    assert!((650..850).contains(&kept));
+    let (gradients, ()) = layer.backward(input.clone(), mask);
+    assert!(output.iter().eq(gradients.iter()));
+
+    layer.set_mode(Mode::Inference);
+    let (output, mask) = layer.forward(input.clone());
+    assert!(mask.is_none());
+    assert!(output.iter().eq(input.iter()));
+}
+
+#[test]
+fn seeded_dropout_is_reproducible() {
+    use crate::array::Array1D;
+
+    let input = Array1D::<100>::from([1.0; 100].as_slice());
+    let mut a = Dropout::<50>::seeded(7);
+    let mut b = a;
+    let mask = |layer: &Dropout<50>, sample| layer.forward_in(input.clone(), ForwardContext { sample, ..Default::default() }).0;
+    assert!(mask(&a, 0).iter().eq(mask(&b, 0).iter()));
+    assert!(!mask(&a, 0).iter().eq(mask(&a, 1).iter()));
+
+    // a batch draws every sample's mask from its index, like `forward_in` does
+    let (outputs, _) = Layer::<Array1D<100>>::forward_batch(&a, vec![input.clone(); 3]);
+    assert!(outputs[2].iter().eq(mask(&a, 2).iter()));
+    Layer::<Array1D<100>>::apply_gradients(&mut a, (), 1.0);
+    assert!(mask(&a, 0).iter().eq(mask(&b, 0).iter()));
+    Layer::<Array1D<100>>::end_batch(&mut a);
+    assert!(!mask(&a, 0).iter().eq(mask(&b, 0).iter()));
+    Layer::<Array1D<100>>::end_batch(&mut b);
+    assert!(mask(&a, 0).iter().eq(mask(&b, 0).iter()));
+}
@@ -3,20 +3,190 @@ use std::marker::PhantomData;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::array::Tensor;
+
+pub mod chain;
 pub mod convolution;
+pub mod padding;
 pub mod pooling;
 pub mod bias;
+pub mod batch_norm;
+pub mod dropout;
 pub mod dense;
 pub mod reshape;
+pub mod trace;
+
+/// Whether layers behave like they do while learning (dropping units, using batch statistics) or like they do for predictions
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Mode {
+    #[default]
+    Training,
+    Inference,
+}
+
+/// What `forward_in` knows about the layers after it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ForwardContext {
+    /// The next layer keeps the output as its input, so `backward_with` gets it back
+    pub output_kept: bool,
+    /// Index of the sample in its batch, layers drawing random numbers seed with it
+    pub sample: usize,
+}
+
+/// One step on the way to a parameter, a field or an index into an array of tensors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathSegment {
+    Field(&'static str),
+    Index(usize),
+}
+
+/// Where a parameter sits, outermost segment first. Displays as `next.step.weights`.
+/// `LayerChain` links its own segment in front of the path on the stack, so visiting doesn't allocate
+#[derive(Clone, Copy, Debug)]
+pub struct ParameterPath<'a> {
+    pub segment: PathSegment,
+    pub inner: Option<&'a ParameterPath<'a>>,
+}
+impl<'a> ParameterPath<'a> {
+    pub fn new(segment: PathSegment, inner: Option<&'a ParameterPath<'a>>) -> Self {
+        Self { segment, inner }
+    }
+    /// the path of a parameter stored in the field `name` of a layer
+    pub fn field(name: &'static str) -> Self {
+        Self::new(PathSegment::Field(name), None)
+    }
+    pub fn segments(&self) -> impl Iterator<Item = PathSegment> + '_ {
+        std::iter::successors(Some(self), |path| path.inner).map(|path| path.segment)
+    }
+}
+impl std::fmt::Display for ParameterPath<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.segments().enumerate() {
+            if i != 0 {
+                f.write_str(".")?;
+            }
+            match segment {
+                PathSegment::Field(name) => f.write_str(name)?,
+                PathSegment::Index(index) => write!(f, "{index}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A tensor of trainable parameters, `shape` lists the outermost dimension first.
+/// `decays` is set for weights and kernels, biases, norm scales and activation parameters are left out of weight decay
+#[derive(Debug)]
+pub struct Parameter<'a> {
+    pub path: ParameterPath<'a>,
+    pub decays: bool,
+    pub shape: &'a [usize],
+    pub values: &'a [f32],
+}
+#[derive(Debug)]
+pub struct ParameterMut<'a> {
+    pub path: ParameterPath<'a>,
+    pub decays: bool,
+    pub shape: &'a [usize],
+    pub values: &'a mut [f32],
+}
 
 pub trait Layer<I> {
     type Output;
     type ForwardData;
-    type Gradients;
+    type Gradients: Tensor;
 
     fn forward(&self, input: I) -> (Self::Output, Self::ForwardData);
+    /// Same output as `forward`, without building the `ForwardData` that only `backward` needs
+    fn infer(&self, input: I) -> Self::Output {
+        self.forward(input).0
+    }
     fn backward(&self, forward: Self::Output, forward_data: Self::ForwardData) -> (I, Self::Gradients);
     fn apply_gradients(&mut self, gradients: Self::Gradients, multiplier: f32);
+
+    /// Whether `ForwardData` is the input and `backward_with` returns it, so the layer before doesn't need to keep its output
+    const KEEPS_INPUT: bool = false;
+    /// `forward`, but the layer can leave out of its `ForwardData` what `backward_with` gets back through `context`
+    fn forward_in(&self, input: I, _context: ForwardContext) -> (Self::Output, Self::ForwardData) {
+        self.forward(input)
+    }
+    /// `backward` for `forward_in`, with the output if the next layer kept it. Also returns the input if `KEEPS_INPUT`
+    fn backward_with(&self, forward: Self::Output, forward_data: Self::ForwardData, _output: Option<Self::Output>) -> (I, Self::Gradients, Option<I>) {
+        let (input, gradients) = self.backward(forward, forward_data);
+        (input, gradients, None)
+    }
+
+    /// Calls `visitor` with every trainable parameter tensor, `LayerChain` puts its fields in front of the path (`next.step.weights`).
+    /// Layers without parameters implement both as empty.
+    fn visit_parameters(&self, visitor: &mut dyn FnMut(Parameter));
+    fn visit_parameters_mut(&mut self, visitor: &mut dyn FnMut(ParameterMut));
+    /// Multiplies every parameter that `decays` by `multiplier`, used for decoupled weight decay.
+    fn decay_weights(&mut self, multiplier: f32) {
+        self.visit_parameters_mut(&mut |parameter| {
+            if parameter.decays {
+                for x in parameter.values {
+                    *x *= multiplier;
+                }
+            }
+        });
+    }
+    fn parameter_count(&self) -> usize {
+        let mut count = 0;
+        self.visit_parameters(&mut |parameter| count += parameter.values.len());
+        count
+    }
+
+    /// An empty accumulator for `accumulate_gradients`
+    fn zeroed_gradients(&self) -> Self::Gradients {
+        Self::Gradients::zeroed()
+    }
+    /// Adds `gradients * multiplier` into `accumulator`, so a batch can be summed without keeping every sample's gradients
+    fn accumulate_gradients(&self, accumulator: &mut Self::Gradients, gradients: Self::Gradients, multiplier: f32) {
+        for (accumulator, gradients) in accumulator.slices_mut().zip(gradients.slices()) {
+            for (accumulator, gradient) in accumulator.iter_mut().zip(gradients) {
+                *accumulator += *gradient * multiplier;
+            }
+        }
+    }
+
+    /// Runs a whole batch through the layer
+    fn forward_batch(&self, inputs: Vec<I>) -> (Vec<Self::Output>, Vec<Self::ForwardData>) {
+        self.forward_batch_in(inputs, ForwardContext::default())
+    }
+    /// Backward pass for `forward_batch`, the returned gradients are summed over the batch
+    fn backward_batch(&self, forwards: Vec<Self::Output>, forward_data: Vec<Self::ForwardData>) -> (Vec<I>, Self::Gradients) {
+        let (inputs, gradients, _) = self.backward_batch_with(forwards, forward_data, None);
+        (inputs, gradients)
+    }
+    /// `forward_in` for a batch, `context.sample` is the index of the first input.
+    /// Layers that look at the batch as a whole (like batch norm) or can do it faster at once (like dense) override this.
+    fn forward_batch_in(&self, inputs: Vec<I>, context: ForwardContext) -> (Vec<Self::Output>, Vec<Self::ForwardData>) {
+        inputs.into_iter().enumerate().map(|(i, input)| self.forward_in(input, ForwardContext { sample: context.sample + i, ..context })).unzip()
+    }
+    /// `backward_with` for `forward_batch_in`
+    fn backward_batch_with(&self, forwards: Vec<Self::Output>, forward_data: Vec<Self::ForwardData>, outputs: Option<Vec<Self::Output>>) -> (Vec<I>, Self::Gradients, Option<Vec<I>>) {
+        let mut gradients = self.zeroed_gradients();
+        let mut outputs = outputs.map(Vec::into_iter);
+        let (inputs, kept): (Vec<_>, Vec<_>) = forwards.into_iter().zip(forward_data).map(|(forward, forward_data)| {
+            let (input, sample, kept) = self.backward_with(forward, forward_data, outputs.as_mut().and_then(Iterator::next));
+            self.accumulate_gradients(&mut gradients, sample, 1.0);
+            (input, kept)
+        }).unzip();
+        (inputs, gradients, kept.into_iter().collect())
+    }
+    /// Whether `forward_batch` normalizes with statistics over the whole batch, `Network` can then only learn from whole batches.
+    /// Otherwise it splits the batch into micro-batches.
+    fn uses_batch_statistics(&self) -> bool {
+        false
+    }
+    /// Called once per training batch with the forward data of one of its samples, so layers can keep running statistics.
+    /// Batch statistics are shared by the forward data of every sample.
+    fn update_statistics(&mut self, _forward_data: &Self::ForwardData) {}
+    /// Called by `Network` after every training batch has been applied, for layers that change from batch to batch (like dropout's masks)
+    fn end_batch(&mut self) {}
+    fn set_mode(&mut self, _mode: Mode) {}
 }
 impl<I> Layer<I> for () {
     type Output = I;
@@ -35,6 +205,10 @@ impl<I> Layer<I> for () {
     
     #[inline]
     fn apply_gradients(&mut self, _gradients: Self::Gradients, _multiplier: f32) {}
+
+    fn visit_parameters(&self, _visitor: &mut dyn FnMut(Parameter)) {}
+
+    fn visit_parameters_mut(&mut self, _visitor: &mut dyn FnMut(ParameterMut)) {}
 }
 
 #[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
@@ -74,15 +248,30 @@ where
 
     #[inline]
     fn forward(&self, input: I) -> (Self::Output, Self::ForwardData) {
-        let intermediate = self.step.forward(input);
-        let output = self.next.forward(intermediate.0);
-        (output.0, (intermediate.1, output.1))
+        self.forward_in(input, ForwardContext::default())
+    }
+    #[inline]
+    fn infer(&self, input: I) -> Self::Output {
+        self.next.infer(self.step.infer(input))
     }
     #[inline]
     fn backward(&self, forward: Self::Output, forward_data: Self::ForwardData) -> (I, Self::Gradients) {
-        let intermediate = self.next.backward(forward, forward_data.1);
-        let output = self.step.backward(intermediate.0, forward_data.0);
-        (output.0, (output.1, intermediate.1))
+        let (input, gradients, _) = self.backward_with(forward, forward_data, None);
+        (input, gradients)
+    }
+
+    const KEEPS_INPUT: bool = S::KEEPS_INPUT;
+    #[inline]
+    fn forward_in(&self, input: I, context: ForwardContext) -> (Self::Output, Self::ForwardData) {
+        let intermediate = self.step.forward_in(input, ForwardContext { output_kept: N::KEEPS_INPUT, ..context });
+        let output = self.next.forward_in(intermediate.0, context);
+        (output.0, (intermediate.1, output.1))
+    }
+    #[inline]
+    fn backward_with(&self, forward: Self::Output, forward_data: Self::ForwardData, output: Option<Self::Output>) -> (I, Self::Gradients, Option<I>) {
+        let intermediate = self.next.backward_with(forward, forward_data.1, output);
+        let input = self.step.backward_with(intermediate.0, forward_data.0, intermediate.2);
+        (input.0, (input.1, intermediate.1), input.2)
     }
     
     #[inline]
@@ -90,6 +279,58 @@ where
         self.step.apply_gradients(gradients.0, multiplier);
         self.next.apply_gradients(gradients.1, multiplier);
     }
+
+    fn visit_parameters(&self, visitor: &mut dyn FnMut(Parameter)) {
+        self.step.visit_parameters(&mut |parameter| visitor(Parameter { path: ParameterPath::new(PathSegment::Field("step"), Some(&parameter.path)), ..parameter }));
+        self.next.visit_parameters(&mut |parameter| visitor(Parameter { path: ParameterPath::new(PathSegment::Field("next"), Some(&parameter.path)), ..parameter }));
+    }
+
+    fn visit_parameters_mut(&mut self, visitor: &mut dyn FnMut(ParameterMut)) {
+        self.step.visit_parameters_mut(&mut |parameter| visitor(ParameterMut { path: ParameterPath::new(PathSegment::Field("step"), Some(&parameter.path)), ..parameter }));
+        self.next.visit_parameters_mut(&mut |parameter| visitor(ParameterMut { path: ParameterPath::new(PathSegment::Field("next"), Some(&parameter.path)), ..parameter }));
+    }
+
+    #[inline]
+    fn decay_weights(&mut self, multiplier: f32) {
+        self.step.decay_weights(multiplier);
+        self.next.decay_weights(multiplier);
+    }
+
+    fn forward_batch_in(&self, inputs: Vec<I>, context: ForwardContext) -> (Vec<Self::Output>, Vec<Self::ForwardData>) {
+        let (intermediate, step_data) = self.step.forward_batch_in(inputs, ForwardContext { output_kept: N::KEEPS_INPUT, ..context });
+        let (output, next_data) = self.next.forward_batch_in(intermediate, context);
+        (output, step_data.into_iter().zip(next_data).collect())
+    }
+
+    fn backward_batch_with(&self, forwards: Vec<Self::Output>, forward_data: Vec<Self::ForwardData>, outputs: Option<Vec<Self::Output>>) -> (Vec<I>, Self::Gradients, Option<Vec<I>>) {
+        let (step_data, next_data): (Vec<_>, Vec<_>) = forward_data.into_iter().unzip();
+        let (intermediate, next_gradients, kept) = self.next.backward_batch_with(forwards, next_data, outputs);
+        let (inputs, step_gradients, kept) = self.step.backward_batch_with(intermediate, step_data, kept);
+        (inputs, (step_gradients, next_gradients), kept)
+    }
+
+    #[inline]
+    fn uses_batch_statistics(&self) -> bool {
+        self.step.uses_batch_statistics() || self.next.uses_batch_statistics()
+    }
+
+    #[inline]
+    fn update_statistics(&mut self, forward_data: &Self::ForwardData) {
+        self.step.update_statistics(&forward_data.0);
+        self.next.update_statistics(&forward_data.1);
+    }
+
+    #[inline]
+    fn end_batch(&mut self) {
+        self.step.end_batch();
+        self.next.end_batch();
+    }
+
+    #[inline]
+    fn set_mode(&mut self, mode: Mode) {
+        self.step.set_mode(mode);
+        self.next.set_mode(mode);
+    }
 }
 
 #[macro_export]
@@ -103,4 +344,130 @@ macro_rules! layer_chain {
     ($a:expr, $($tail:expr),+) => {
         LayerChain::new($a, layer_chain!($($tail),+))
     };
-}
\ No newline at end of file
+}
+
+/// Builds a chain the same way as `LayerChain::new(first, ()).push(second)...`, or names its type.
+/// ```ignore
+/// sequential! {
+///     pub type Model = Array1D<2> => [DenseLayer<2, 4>, Sigmoid, DenseLayer<4, 1>];
+/// }
+/// let model: Model = sequential![DenseLayer::random(), Sigmoid::new(), DenseLayer::random()];
+/// ```
+#[macro_export]
+macro_rules! sequential {
+    (@chain $input:ty; $acc:ty;) => {
+        $acc
+    };
+    (@chain $input:ty; $acc:ty; $next:ty $(, $rest:ty)*) => {
+        $crate::sequential!(@chain $input; $crate::layer::LayerChain<$acc, $next, $input>; $($rest),*)
+    };
+    ($(#[$meta:meta])* $vis:vis type $name:ident = $input:ty => [$first:ty $(, $rest:ty)* $(,)?];) => {
+        $(#[$meta])*
+        $vis type $name = $crate::sequential!(@chain $input; $crate::layer::LayerChain<$first, (), $input>; $($rest),*);
+    };
+    ($first:expr $(, $rest:expr)* $(,)?) => {
+        $crate::layer::LayerChain::new($first, ())$(.push($rest))*
+    };
+}
+
+/// Names the type of a `Network` over a `sequential!` chain, with its cost function and label type
+/// ```ignore
+/// network! {
+///     pub type Model = Array1D<2> => [DenseLayer<2, 4>, Sigmoid, DenseLayer<4, 1>] => Mse, Array1D<1>;
+/// }
+/// let network = Model::new(sequential![DenseLayer::random(), Sigmoid::new(), DenseLayer::random()]);
+/// ```
+#[macro_export]
+macro_rules! network {
+    ($(#[$meta:meta])* $vis:vis type $name:ident = $input:ty => [$first:ty $(, $rest:ty)* $(,)?] => $cost:ty, $label:ty;) => {
+        $(#[$meta])*
+        $vis type $name = $crate::Network<
+            $input,
+            $crate::sequential!(@chain $input; $crate::layer::LayerChain<$first, (), $input>; $($rest),*),
+            $cost,
+            <$crate::sequential!(@chain $input; $crate::layer::LayerChain<$first, (), $input>; $($rest),*) as $crate::layer::Layer<$input>>::Output,
+            $label,
+        >;
+    };
+}
+#[test]
+fn chain_parameter_names() {
+    use crate::{activation::sigmoid::Sigmoid, array::Array1D};
+    use dense::DenseLayer;
+
+    let mut chain: LayerChain<_, _, Array1D<2>> = layer_chain!(DenseLayer::<2, 4>::new(), Sigmoid::new(), DenseLayer::<4, 1>::new());
+    let mut names = vec![];
+    chain.visit_parameters(&mut |parameter| names.push((parameter.path.to_string(), parameter.shape.to_vec())));
+    assert_eq!(names, [
+        ("step.weights".to_owned(), vec![4, 2]),
+        ("step.biases".to_owned(), vec![4]),
+        ("next.next.weights".to_owned(), vec![1, 4]),
+        ("next.next.biases".to_owned(), vec![1]),
+    ]);
+    assert_eq!(chain.parameter_count(), 8 + 4 + 4 + 1);
+
+    chain.visit_parameters_mut(&mut |parameter| parameter.values.fill(1.0));
+    chain.decay_weights(0.5);
+    assert_eq!(chain.next.next.weights[0], [0.5; 4]);
+    assert_eq!(chain.next.next.biases[0], 1.0);
+}
+
+#[test]
+fn sequential_matches_its_alias() {
+    use crate::{activation::sigmoid::Sigmoid, array::Array1D, cost::Mse};
+    use dense::DenseLayer;
+
+    network! {
+        type Model = Array1D<2> => [DenseLayer<2, 4>, Sigmoid, DenseLayer<4, 1>] => Mse, Array1D<1>;
+    }
+    let network = Model::new(sequential![DenseLayer::random(), Sigmoid::new(), DenseLayer::random()]);
+    assert_eq!(network.layer.get::<2>().weights[0].len(), 4);
+}
+
+#[test]
+fn infer_matches_forward() {
+    use crate::{activation::{sigmoid::Sigmoid, softmax::Softmax}, array::Array1D};
+    use self::{batch_norm::BatchNorm, convolution::Convolution, dense::DenseLayer, pooling::MaxPooling, reshape::{Flatten, Shape}};
+
+    let mut chain = sequential![
+        Shape::<16, 4, 4>::default(),
+        Convolution::<3>::random(),
+        BatchNorm::<1>::new(),
+        Sigmoid::new(),
+        MaxPooling::<2, 2, 2>::default(),
+        Flatten::<4, 2, 2>::default(),
+        DenseLayer::<4, 3>::random(),
+        Softmax::new(),
+    ];
+    chain.set_mode(Mode::Inference);
+    chain.visit_parameters_mut(&mut |parameter| parameter.values.iter_mut().for_each(|x| *x += 0.5));
+    let input = Array1D::<16>::from((0..16).map(|x| (x as f32 * 0.7).sin()).collect::<Vec<_>>().as_slice());
+    let (output, _) = chain.forward(input.clone());
+    for (a, b) in chain.infer(input).iter().zip(output.iter()) {
+        assert!((a - b).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn kept_outputs_are_not_copied() {
+    use crate::{activation::sigmoid::Sigmoid, array::Array1D, gradcheck::check_linear};
+    use dense::DenseLayer;
+
+    let input = Array1D::<3>::from([0.5, -1.0, 2.0].as_slice());
+    let nested: LayerChain<_, _, Array1D<3>> = layer_chain!(DenseLayer::<3, 4>::random(), Sigmoid::new(), DenseLayer::<4, 2>::random(), Sigmoid::new());
+    let pushed = sequential![DenseLayer::<3, 4>::random(), Sigmoid::new(), DenseLayer::<4, 2>::random(), Sigmoid::new()];
+
+    // the dense layer after the hidden sigmoid keeps its output, only the last sigmoid needs its own copy
+    let (_, (_, (hidden, (_, output)))) = nested.forward(input.clone());
+    assert!(hidden.is_none() && output.is_some());
+    let (_, (((_, hidden), _), output)) = pushed.forward(input.clone());
+    assert!(hidden.is_none() && output.is_some());
+    let (outputs, forward_data) = nested.forward_batch(vec![input.clone(); 2]);
+    assert!(forward_data.iter().all(|(_, (hidden, (_, output)))| hidden.is_none() && output.is_some()));
+    let (inputs, _) = nested.backward_batch(outputs.clone(), forward_data);
+    let (single, _) = nested.backward(outputs[1].clone(), nested.forward(input.clone()).1);
+    assert!(inputs[1].iter().eq(single.iter()));
+
+    assert!(check_linear(&nested, &input, 1e-3).max_error() < 1e-2);
+    assert!(check_linear(&pushed, &input, 1e-3).max_error() < 1e-2);
+}
@@ -0,0 +1,91 @@
+use typenum::{Unsigned, U0, U1};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub trait Padding {
+    /// `U1` if the kernel isn't allowed to leave the input (valid), `U0` if the input is padded so the output keeps its size
+    type Trim: Unsigned;
+
+    /// maps a position on the padded input back into `0..len`, `None` means the padded value is 0
+    fn index(position: isize, len: usize) -> Option<usize>;
+}
+
+/// no padding, the output shrinks by the kernel size
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Valid;
+
+impl Padding for Valid {
+    type Trim = U1;
+
+    #[inline]
+    fn index(position: isize, len: usize) -> Option<usize> {
+        (0..len as isize).contains(&position).then_some(position as usize)
+    }
+}
+
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Zeros;
+
+impl Padding for Zeros {
+    type Trim = U0;
+
+    #[inline]
+    fn index(position: isize, len: usize) -> Option<usize> {
+        (0..len as isize).contains(&position).then_some(position as usize)
+    }
+}
+
+/// mirrors the input without repeating the edge: `c b | a b c | b a`
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Reflect;
+
+impl Padding for Reflect {
+    type Trim = U0;
+
+    #[inline]
+    fn index(position: isize, len: usize) -> Option<usize> {
+        if len == 1 {
+            return Some(0);
+        }
+        let period = 2 * (len as isize - 1);
+        let position = position.rem_euclid(period);
+        Some(if position < len as isize { position } else { period - position } as usize)
+    }
+}
+
+/// repeats the edge: `a a | a b c | c c`
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Replicate;
+
+impl Padding for Replicate {
+    type Trim = U0;
+
+    #[inline]
+    fn index(position: isize, len: usize) -> Option<usize> {
+        Some(position.clamp(0, len as isize - 1) as usize)
+    }
+}
+
+/// wraps around: `b c | a b c | a b`
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Circular;
+
+impl Padding for Circular {
+    type Trim = U0;
+
+    #[inline]
+    fn index(position: isize, len: usize) -> Option<usize> {
+        Some(position.rem_euclid(len as isize) as usize)
+    }
+}
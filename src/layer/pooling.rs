@@ -4,9 +4,9 @@ use std::ops::{Mul, Rem};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::array::Array2D;
+use crate::array::{Array2D, Array3D};
 
-use super::Layer;
+use super::{chain::Leaf, Layer, Parameter, ParameterMut};
 
 #[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
@@ -16,31 +16,17 @@ where
     Const<N>: ToUInt,
     U<N>: Cmp<U<65536>, Output = Less> {}
 
-impl<const X: usize, const Y: usize, const N: usize, const A: usize, const B: usize> Layer<Array2D<X, Y>> for MaxPooling<N, A, B>
+impl<const N: usize, const A: usize, const B: usize> Leaf for MaxPooling<N, A, B>
 where
     Const<N>: ToUInt,
-    Const<X>: ToUInt,
-    Const<Y>: ToUInt,
-    Const<A>: ToUInt,
-    Const<B>: ToUInt,
-
-    U<N>: Cmp<U<65536>, Output = Less>,
-    U<X>: Rem<U<N>, Output = U<0>>,
-    U<Y>: Rem<U<N>, Output = U<0>>,
-    // A = X/N
-    // B = Y/N
-    U<A>: Mul<U<N>, Output = U<X>>,
-    U<B>: Mul<U<N>, Output = U<Y>>, {
-    type Output = Array2D<A, B>;
-
-    type ForwardData = Array2D<A, B>;
-
-    type Gradients = ();
-
-    fn forward(&self, input: Array2D<X, Y>) -> (Self::Output, Self::ForwardData) {
-        let mut out = Array2D::new();
-        let mut forward_data = Array2D::new(); // TODO: Array2d<T>
+    U<N>: Cmp<U<65536>, Output = Less>, {}
 
+impl<const N: usize, const A: usize, const B: usize> MaxPooling<N, A, B>
+where
+    Const<N>: ToUInt,
+    U<N>: Cmp<U<65536>, Output = Less> {
+    /// `forward_data` receives the packed position of every maximum, `None` when only the output is needed
+    fn pool<const X: usize, const Y: usize>(input: &[[f32; X]; Y], out: &mut [[f32; A]; B], mut forward_data: Option<&mut [[f32; A]; B]>) {
         for chunk_y in 0..B {
             for chunk_x in 0..A {
                 let (mut max_x, mut max_y) = (N, N); // set to an invalid value to catch errors (should be overwritten)
@@ -50,7 +36,7 @@ where
                     for x in 0..N {
                         let x_index = chunk_x * N + x;
                         let y_index = chunk_y * N + y;
-                        let val = input.array[y_index][x_index];
+                        let val = input[y_index][x_index];
 
                         if val > max {
                             max = val;
@@ -60,29 +46,126 @@ where
                     }
                 }
 
-                forward_data.array[chunk_y][chunk_x] = f32::from_bits(((max_y << 16) | max_x) as u32);
-                out.array[chunk_y][chunk_x] = max;
+                if let Some(forward_data) = forward_data.as_deref_mut() {
+                    forward_data[chunk_y][chunk_x] = f32::from_bits(((max_y << 16) | max_x) as u32);
+                }
+                out[chunk_y][chunk_x] = max;
             }
         }
-        (out, forward_data)
     }
 
-    fn backward(&self, forward: Self::Output, forward_data: Self::ForwardData) -> (Array2D<X, Y>, Self::Gradients) {
-        let mut out = Array2D::new();
+    fn unpool<const X: usize, const Y: usize>(forward: &[[f32; A]; B], forward_data: &[[f32; A]; B], out: &mut [[f32; X]; Y]) {
         for chunk_y in 0..B {
             for chunk_x in 0..A {
-                let packed = forward_data.array[chunk_y][chunk_x].to_bits();
+                let packed = forward_data[chunk_y][chunk_x].to_bits();
                 let x = packed & 0xffff;
                 let y = (packed >> 16) & 0xffff;
 
                 let x_index = chunk_x * N + x as usize;
                 let y_index = chunk_y * N + y as usize;
 
-                out.array[y_index][x_index] = forward[chunk_y][chunk_x];
+                out[y_index][x_index] = forward[chunk_y][chunk_x];
             }
         }
+    }
+}
+
+impl<const X: usize, const Y: usize, const N: usize, const A: usize, const B: usize> Layer<Array2D<X, Y>> for MaxPooling<N, A, B>
+where
+    Const<N>: ToUInt,
+    Const<X>: ToUInt,
+    Const<Y>: ToUInt,
+    Const<A>: ToUInt,
+    Const<B>: ToUInt,
+
+    U<N>: Cmp<U<65536>, Output = Less>,
+    U<X>: Rem<U<N>, Output = U<0>>,
+    U<Y>: Rem<U<N>, Output = U<0>>,
+    // A = X/N
+    // B = Y/N
+    U<A>: Mul<U<N>, Output = U<X>>,
+    U<B>: Mul<U<N>, Output = U<Y>>, {
+    type Output = Array2D<A, B>;
+
+    type ForwardData = Array2D<A, B>;
+
+    type Gradients = ();
+
+    fn forward(&self, input: Array2D<X, Y>) -> (Self::Output, Self::ForwardData) {
+        let mut out = Array2D::new();
+        let mut forward_data = Array2D::new(); // TODO: Array2d<T>
+        Self::pool(&input, &mut out, Some(&mut forward_data));
+        (out, forward_data)
+    }
+
+    fn infer(&self, input: Array2D<X, Y>) -> Self::Output {
+        let mut out = Array2D::new();
+        Self::pool(&input, &mut out, None);
+        out
+    }
+
+    fn backward(&self, forward: Self::Output, forward_data: Self::ForwardData) -> (Array2D<X, Y>, Self::Gradients) {
+        let mut out = Array2D::new();
+        Self::unpool(&forward, &forward_data, &mut out);
         (out, ())
     }
 
     fn apply_gradients(&mut self, _gradients: Self::Gradients, _multiplier: f32) {}
+
+    fn visit_parameters(&self, _visitor: &mut dyn FnMut(Parameter)) {}
+
+    fn visit_parameters_mut(&mut self, _visitor: &mut dyn FnMut(ParameterMut)) {}
+}
+
+impl<const C: usize, const X: usize, const Y: usize, const N: usize, const A: usize, const B: usize> Layer<Array3D<C, X, Y>> for MaxPooling<N, A, B>
+where
+    Const<N>: ToUInt,
+    Const<X>: ToUInt,
+    Const<Y>: ToUInt,
+    Const<A>: ToUInt,
+    Const<B>: ToUInt,
+
+    U<N>: Cmp<U<65536>, Output = Less>,
+    U<X>: Rem<U<N>, Output = U<0>>,
+    U<Y>: Rem<U<N>, Output = U<0>>,
+    // A = X/N
+    // B = Y/N
+    U<A>: Mul<U<N>, Output = U<X>>,
+    U<B>: Mul<U<N>, Output = U<Y>>, {
+    type Output = Array3D<C, A, B>;
+
+    type ForwardData = Array3D<C, A, B>;
+
+    type Gradients = ();
+
+    fn forward(&self, input: Array3D<C, X, Y>) -> (Self::Output, Self::ForwardData) {
+        let mut out = Array3D::new();
+        let mut forward_data = Array3D::new();
+        for ((input, out), forward_data) in input.iter().zip(out.iter_mut()).zip(forward_data.iter_mut()) {
+            Self::pool(input, out, Some(forward_data));
+        }
+        (out, forward_data)
+    }
+
+    fn infer(&self, input: Array3D<C, X, Y>) -> Self::Output {
+        let mut out = Array3D::new();
+        for (input, out) in input.iter().zip(out.iter_mut()) {
+            Self::pool(input, out, None);
+        }
+        out
+    }
+
+    fn backward(&self, forward: Self::Output, forward_data: Self::ForwardData) -> (Array3D<C, X, Y>, Self::Gradients) {
+        let mut out = Array3D::new();
+        for ((forward, forward_data), out) in forward.iter().zip(forward_data.iter()).zip(out.iter_mut()) {
+            Self::unpool(forward, forward_data, out);
+        }
+        (out, ())
+    }
+
+    fn apply_gradients(&mut self, _gradients: Self::Gradients, _multiplier: f32) {}
+
+    fn visit_parameters(&self, _visitor: &mut dyn FnMut(Parameter)) {}
+
+    fn visit_parameters_mut(&mut self, _visitor: &mut dyn FnMut(ParameterMut)) {}
 }
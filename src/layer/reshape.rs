@@ -4,9 +4,9 @@ use std::ops::Mul;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::array::{Array1D, Array2D};
+use crate::array::{Array1D, Array2D, Array3D};
 
-use super::Layer;
+use super::{chain::Leaf, Layer, Parameter, ParameterMut};
 
 #[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
@@ -18,6 +18,13 @@ where
     Const<Y>: ToUInt,
     U<X>: Mul<U<Y>, Output = U<N>>, {}
 
+impl<const N: usize, const X: usize, const Y: usize> Leaf for Shape<N, X, Y>
+where
+    Const<N>: ToUInt,
+    Const<X>: ToUInt,
+    Const<Y>: ToUInt,
+    U<X>: Mul<U<Y>, Output = U<N>>, {}
+
 impl<const N: usize, const X: usize, const Y: usize> Layer<Array1D<N>> for Shape<N, X, Y>
 where
     Const<N>: ToUInt,
@@ -42,6 +49,41 @@ where
 
     #[inline]
     fn apply_gradients(&mut self, _gradients: Self::Gradients, _multiplier: f32) {}
+
+    fn visit_parameters(&self, _visitor: &mut dyn FnMut(Parameter)) {}
+
+    fn visit_parameters_mut(&mut self, _visitor: &mut dyn FnMut(ParameterMut)) {}
+}
+
+/// Shapes every channel, `C` vectors of length `N` become `C` planes of `X` by `Y`
+impl<const N: usize, const C: usize, const X: usize, const Y: usize> Layer<Array2D<N, C>> for Shape<N, X, Y>
+where
+    Const<N>: ToUInt,
+    Const<X>: ToUInt,
+    Const<Y>: ToUInt,
+    U<X>: Mul<U<Y>, Output = U<N>>, {
+    type Output = Array3D<C, X, Y>;
+
+    type ForwardData = ();
+
+    type Gradients = ();
+
+    #[inline]
+    fn forward(&self, input: Array2D<N, C>) -> (Self::Output, Self::ForwardData) {
+        (Array3D::from(unsafe { std::mem::transmute::<Box<[[f32; N]; C]>, Box<[[[f32; X]; Y]; C]>>(input.array) }), ())
+    }
+
+    #[inline]
+    fn backward(&self, forward: Self::Output, _forward_data: Self::ForwardData) -> (Array2D<N, C>, Self::Gradients) {
+        (Array2D::from(unsafe { std::mem::transmute::<Box<[[[f32; X]; Y]; C]>, Box<[[f32; N]; C]>>(forward.array) }), ())
+    }
+
+    #[inline]
+    fn apply_gradients(&mut self, _gradients: Self::Gradients, _multiplier: f32) {}
+
+    fn visit_parameters(&self, _visitor: &mut dyn FnMut(Parameter)) {}
+
+    fn visit_parameters_mut(&mut self, _visitor: &mut dyn FnMut(ParameterMut)) {}
 }
 
 #[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
@@ -54,6 +96,13 @@ where
     Const<Y>: ToUInt,
     U<X>: Mul<U<Y>, Output = U<N>>, {}
 
+impl<const N: usize, const X: usize, const Y: usize> Leaf for Flatten<N, X, Y>
+where
+    Const<N>: ToUInt,
+    Const<X>: ToUInt,
+    Const<Y>: ToUInt,
+    U<X>: Mul<U<Y>, Output = U<N>>, {}
+
 impl<const N: usize, const X: usize, const Y: usize> Layer<Array2D<X, Y>> for Flatten<N, X, Y>
 where
     Const<N>: ToUInt,
@@ -78,4 +127,54 @@ where
 
     #[inline]
     fn apply_gradients(&mut self, _gradients: Self::Gradients, _multiplier: f32) {}
-}
\ No newline at end of file
+
+    fn visit_parameters(&self, _visitor: &mut dyn FnMut(Parameter)) {}
+
+    fn visit_parameters_mut(&mut self, _visitor: &mut dyn FnMut(ParameterMut)) {}
+}
+
+/// Flattens every channel, the inverse of `Shape` over channels
+impl<const N: usize, const C: usize, const X: usize, const Y: usize> Layer<Array3D<C, X, Y>> for Flatten<N, X, Y>
+where
+    Const<N>: ToUInt,
+    Const<X>: ToUInt,
+    Const<Y>: ToUInt,
+    U<X>: Mul<U<Y>, Output = U<N>>, {
+    type Output = Array2D<N, C>;
+
+    type ForwardData = ();
+
+    type Gradients = ();
+
+    #[inline]
+    fn forward(&self, input: Array3D<C, X, Y>) -> (Self::Output, Self::ForwardData) {
+        (Array2D::from(unsafe { std::mem::transmute::<Box<[[[f32; X]; Y]; C]>, Box<[[f32; N]; C]>>(input.array) }), ())
+    }
+
+    #[inline]
+    fn backward(&self, forward: Self::Output, _forward_data: Self::ForwardData) -> (Array3D<C, X, Y>, Self::Gradients) {
+        (Array3D::from(unsafe { std::mem::transmute::<Box<[[f32; N]; C]>, Box<[[[f32; X]; Y]; C]>>(forward.array) }), ())
+    }
+
+    #[inline]
+    fn apply_gradients(&mut self, _gradients: Self::Gradients, _multiplier: f32) {}
+
+    fn visit_parameters(&self, _visitor: &mut dyn FnMut(Parameter)) {}
+
+    fn visit_parameters_mut(&mut self, _visitor: &mut dyn FnMut(ParameterMut)) {}
+}
+
+#[test]
+fn channels_round_trip() {
+    let mut input = Array3D::<3, 4, 2>::new();
+    for (i, x) in input.as_flattened_mut().as_flattened_mut().iter_mut().enumerate() {
+        *x = i as f32;
+    }
+    // a flat vector goes through a stack of channel vectors on its way to channels of planes
+    let (channels, _) = Flatten::<8, 4, 2>::default().forward(input.clone());
+    let (flat, _) = Flatten::<24, 8, 3>::default().forward(channels);
+    assert_eq!(flat[13], 13.0);
+    let (channels, _) = Shape::<24, 8, 3>::default().forward(flat);
+    let (planes, _) = Shape::<8, 4, 2>::default().forward(channels);
+    assert_eq!(planes.as_ref(), input.as_ref());
+}
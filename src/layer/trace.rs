@@ -0,0 +1,64 @@
+use std::any::Any;
+
+use crate::array::Tensor;
+
+use super::{chain::Leaf, Layer, LayerChain};
+
+/// Receives the output of every layer from `Trace::forward_traced`
+pub trait TraceVisitor {
+    fn visit<T: Tensor + Any>(&mut self, index: usize, output: &T);
+}
+impl<F: FnMut(usize, &dyn Any)> TraceVisitor for F {
+    fn visit<T: Tensor + Any>(&mut self, index: usize, output: &T) {
+        self(index, output)
+    }
+}
+
+pub trait Trace<I>: Layer<I> {
+    /// Runs the layers numbering them from `index` and skipping `()`, returns the output and the next free index
+    fn trace<V: TraceVisitor>(&self, input: I, index: usize, visitor: &mut V) -> (Self::Output, usize);
+
+    /// Same as `forward`, but calls `visitor` with every intermediate output and the index of the layer it came from
+    fn forward_traced<V: TraceVisitor>(&self, input: I, visitor: &mut V) -> Self::Output {
+        self.trace(input, 0, visitor).0
+    }
+}
+impl<I> Trace<I> for () {
+    fn trace<V: TraceVisitor>(&self, input: I, index: usize, _visitor: &mut V) -> (I, usize) {
+        (input, index)
+    }
+}
+impl<T: Leaf + Layer<I>, I> Trace<I> for T
+where
+    T::Output: Tensor + Any, {
+    fn trace<V: TraceVisitor>(&self, input: I, index: usize, visitor: &mut V) -> (Self::Output, usize) {
+        let output = self.forward(input).0;
+        visitor.visit(index, &output);
+        (output, index + 1)
+    }
+}
+impl<S: Trace<I>, N: Trace<S::Output>, I> Trace<I> for LayerChain<S, N, I> {
+    fn trace<V: TraceVisitor>(&self, input: I, index: usize, visitor: &mut V) -> (Self::Output, usize) {
+        let (intermediate, index) = self.step.trace(input, index, visitor);
+        self.next.trace(intermediate, index, visitor)
+    }
+}
+
+#[test]
+fn traces_every_layer() {
+    use crate::{activation::relu::Relu, array::Array1D, layer::dense::DenseLayer, layer_chain};
+
+    let chain: LayerChain<_, _, Array1D<3>> = layer_chain!(DenseLayer::<3, 4>::random(), Relu::new(), DenseLayer::<4, 2>::random());
+    let input = Array1D::from([1.0, -0.5, 0.25].as_slice());
+    let mut sizes = vec![];
+    let output = chain.forward_traced(input.clone(), &mut |index, output: &dyn Any| {
+        let size = if let Some(output) = output.downcast_ref::<Array1D<4>>() {
+            output.len()
+        } else {
+            output.downcast_ref::<Array1D<2>>().unwrap().len()
+        };
+        sizes.push((index, size));
+    });
+    assert_eq!(sizes, [(0, 4), (1, 4), (2, 2)]);
+    assert!(output.iter().eq(chain.forward(input).0.iter()));
+}
@@ -5,20 +5,28 @@ use array::Array1D;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::{cost::CostFunction, layer::Layer};
+use crate::{cost::CostFunction, layer::{trace::{Trace, TraceVisitor}, ForwardContext, Layer, Mode}, optimizer::{sgd::Sgd, Optimizer}};
 
 pub mod cost;
 pub mod layer;
 pub mod activation;
 pub mod array;
+pub mod optimizer;
+pub mod initializer;
+pub mod gradcheck;
+mod gemm;
+mod convolve;
+
+/// How many samples `learn_batch` runs through `Layer::forward_batch` at once, so only their forward data is held at a time
+pub const MICRO_BATCH_SIZE: usize = 32;
 
 #[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug, Default)]
 pub struct Network<I, L: Layer<I>, C: CostFunction<P, E>, P, E> {
     pub layer: L,
+    pub cost: C,
     _input_marker: PhantomData<I>,
-    _cost_marker: PhantomData<C>,
     _predicted_marker: PhantomData<P>,
     _label_marker: PhantomData<E>,
 }
@@ -29,39 +37,130 @@ impl<I, L: Layer<I>, C: CostFunction<P, E>, P, E> Network<I, L, C, P, E> {
     }
 }
 impl<I, C: CostFunction<L::Output, E>, E, L: Layer<I>> Network<I, L, C, L::Output, E> {
-    pub fn new(layer: L) -> Self {
+    pub fn new(layer: L) -> Self
+    where
+        C: Default, {
+        Self::with_cost(layer, C::default())
+    }
+    pub fn with_cost(layer: L, cost: C) -> Self {
         Self {
             layer,
+            cost,
             _input_marker: PhantomData,
-            _cost_marker: PhantomData,
             _predicted_marker: PhantomData,
             _label_marker: PhantomData,
         }
     }
+    /// Only the output of `forward`, skips the data `backward` would need
+    pub fn infer(&self, input: I) -> L::Output {
+        self.layer.infer(input)
+    }
+    /// Same as `forward`, but calls `visitor` with every layer's output, see `Trace`
+    pub fn forward_traced<V: TraceVisitor>(&self, input: I, visitor: &mut V) -> L::Output
+    where
+        L: Trace<I>, {
+        self.layer.forward_traced(input, visitor)
+    }
 }
 impl<I, L: Layer<I, Output = Array1D<N>>, C: CostFunction<L::Output, E>, E, const N: usize> Network<I, L, C, L::Output, E> {
     pub fn forward(&self, input: I) -> (L::Output, L::ForwardData) {
         self.layer.forward(input)
     }
-    fn backwards(&self, output: &L::Output, expected: &E, forward_data: L::ForwardData) -> (I, L::Gradients) {
-        self.layer.backward(C::derivative(output, expected), forward_data)
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.layer.set_mode(mode);
     }
-    fn get_gradients(&self, input: I, expected: E) -> L::Gradients {
-        let forward = self.forward(input);
-        self.backwards(&forward.0, &expected, forward.1).1
+    // takes the layer and cost instead of `self` so worker threads only need to share those
+    fn get_gradients(layer: &L, cost: &C, outputs: Vec<L::Output>, forward_data: Vec<L::ForwardData>, expected: &[E], multiplier: f32) -> L::Gradients {
+        let derivatives = outputs.iter().zip(expected).map(|(output, expected)| {
+            let mut derivative = cost.derivative(output, expected);
+            derivative *= multiplier;
+            derivative
+        }).collect();
+        layer.backward_batch_with(derivatives, forward_data, Some(outputs)).1
+    }
+    /// Forward pass of a batch for `get_gradients`, which hands the last layer its outputs back. `first_sample` is the index of the first input in its batch
+    fn forward_batch(layer: &L, inputs: Vec<I>, first_sample: usize) -> (Vec<L::Output>, Vec<L::ForwardData>) {
+        layer.forward_batch_in(inputs, ForwardContext { output_kept: true, sample: first_sample })
+    }
+    /// Summed gradients of `data` run through `forward_batch` in micro-batches of `MICRO_BATCH_SIZE`
+    fn micro_batch_gradients(layer: &L, cost: &C, data: Vec<(I, E)>, first_sample: usize, multiplier: f32) -> L::Gradients {
+        let mut gradients = layer.zeroed_gradients();
+        let mut sample = first_sample;
+        let mut data = data.into_iter().peekable();
+        while data.peek().is_some() {
+            let (inputs, expected): (Vec<_>, Vec<_>) = data.by_ref().take(MICRO_BATCH_SIZE).unzip();
+            let size = inputs.len();
+            let (outputs, forward_data) = Self::forward_batch(layer, inputs, sample);
+            let micro_batch = Self::get_gradients(layer, cost, outputs, forward_data, &expected, multiplier);
+            layer.accumulate_gradients(&mut gradients, micro_batch, 1.0);
+            sample += size;
+        }
+        gradients
     }
     pub fn learn_batch(&mut self, data: Vec<(I, E)>, learn_rate: f32) {
+        self.learn_batch_with(data, &mut Sgd::new(learn_rate));
+    }
+    pub fn learn_batch_with<O: Optimizer<L::Gradients>>(&mut self, data: Vec<(I, E)>, optimizer: &mut O) {
         let batch_size = data.len();
         if batch_size == 0 {
             return;
         }
-        let mut gradients = Vec::with_capacity(data.len());
-        for (input, expected) in data {
-            gradients.push(self.get_gradients(input, expected));
+        let multiplier = 1.0 / batch_size as f32;
+        let gradients = if self.layer.uses_batch_statistics() {
+            let (inputs, expected): (Vec<_>, Vec<_>) = data.into_iter().unzip();
+            let (outputs, forward_data) = Self::forward_batch(&self.layer, inputs, 0);
+            self.layer.update_statistics(&forward_data[0]);
+            Self::get_gradients(&self.layer, &self.cost, outputs, forward_data, &expected, multiplier)
+        } else {
+            Self::micro_batch_gradients(&self.layer, &self.cost, data, 0, multiplier)
+        };
+        optimizer.step(&mut self.layer, gradients);
+        self.layer.end_batch();
+    }
+    #[cfg(feature = "parallel")]
+    pub fn learn_batch_parallel<O: Optimizer<L::Gradients>>(&mut self, data: Vec<(I, E)>, optimizer: &mut O, shards: usize)
+    where
+        L: Sync,
+        C: Sync,
+        L::Gradients: Send,
+        I: Send,
+        E: Send, {
+        let batch_size = data.len();
+        if batch_size == 0 {
+            return;
+        }
+        if self.layer.uses_batch_statistics() {
+            return self.learn_batch_with(data, optimizer);
         }
-        for gradient in gradients {
-            self.layer.apply_gradients(gradient, -learn_rate / batch_size as f32);
+        // gradients are summed per shard and then in shard order, so the result doesn't depend on the number of cores
+        let shard_size = batch_size.div_ceil(shards.max(1));
+        let shards = batch_size.div_ceil(shard_size);
+        let threads = std::thread::available_parallelism().map_or(1, |x| x.get()).min(shards);
+        let mut work = (0..threads).map(|_| Vec::new()).collect::<Vec<_>>();
+        let mut data = data.into_iter();
+        for shard in 0..shards {
+            work[shard % threads].push((shard, data.by_ref().take(shard_size).collect::<Vec<_>>()));
         }
+
+        let (layer, cost) = (&self.layer, &self.cost);
+        let mut shard_gradients = std::thread::scope(|scope| {
+            let handles = work.into_iter().map(|shards| {
+                scope.spawn(move || {
+                    shards.into_iter().map(|(shard, data)| {
+                        (shard, Self::micro_batch_gradients(layer, cost, data, shard * shard_size, 1.0 / batch_size as f32))
+                    }).collect::<Vec<_>>()
+                })
+            }).collect::<Vec<_>>();
+            handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect::<Vec<_>>()
+        });
+        shard_gradients.sort_by_key(|(shard, _)| *shard);
+
+        let mut gradients = self.layer.zeroed_gradients();
+        for (_, shard) in shard_gradients {
+            self.layer.accumulate_gradients(&mut gradients, shard, 1.0);
+        }
+        optimizer.step(&mut self.layer, gradients);
+        self.layer.end_batch();
     }
 
 }
@@ -88,4 +187,85 @@ impl<I, C: CostFunction<L::Output, E>, E, L: Layer<I>> Network<I, L, C, L::Outpu
         let x = read(path).map_err(Error::new)?;
         rkyv::deserialize::<Network<I, L, C, L::Output, E>, Error>(rkyv::access::<<Self as rkyv::Archive>::Archived, Error>(&x)?)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn parallel_matches_serial() {
+    use crate::{cost::Mse, layer::{dense::DenseLayer, dropout::Dropout, LayerChain}};
+
+    // dropout draws the same masks however the batch is split
+    let mut serial = Network::<_, _, Mse, Array1D<2>, Array1D<2>>::new(layer_chain!(Dropout::<30>::seeded(3), DenseLayer::<3, 2>::random()));
+    let mut parallel = serial.clone();
+    let data = (0..37).map(|x| {
+        let x = x as f32 / 37.0;
+        (Array1D::from([x, 1.0 - x, x * x].as_slice()), Array1D::from([x, -x].as_slice()))
+    }).collect::<Vec<_>>();
+    for i in 0..10 {
+        serial.learn_batch(data.clone(), 0.5);
+        parallel.learn_batch_parallel(data.clone(), &mut Sgd::new(0.5), [1, 5, 37, 64][i % 4]);
+    }
+    for (a, b) in serial.layer.next.weights.iter().flatten().zip(parallel.layer.next.weights.iter().flatten()) {
+        assert!((a - b).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn learn_batch_runs_micro_batches() {
+    use std::cell::Cell;
+    use rand::{rngs::StdRng, SeedableRng};
+    use crate::{cost::Mse, layer::{dense::DenseLayer, Parameter, ParameterMut}};
+
+    // a dense layer that counts its batched forward passes and can't be run sample by sample
+    struct Batched(DenseLayer<3, 2>, Cell<usize>);
+    impl Layer<Array1D<3>> for Batched {
+        type Output = Array1D<2>;
+        type ForwardData = Array1D<3>;
+        type Gradients = <DenseLayer<3, 2> as Layer<Array1D<3>>>::Gradients;
+
+        fn forward(&self, _input: Array1D<3>) -> (Array1D<2>, Array1D<3>) {
+            unreachable!("learned sample by sample")
+        }
+        fn backward(&self, _forward: Array1D<2>, _forward_data: Array1D<3>) -> (Array1D<3>, Self::Gradients) {
+            unreachable!("learned sample by sample")
+        }
+        fn apply_gradients(&mut self, gradients: Self::Gradients, multiplier: f32) {
+            self.0.apply_gradients(gradients, multiplier);
+        }
+        fn visit_parameters(&self, visitor: &mut dyn FnMut(Parameter)) {
+            self.0.visit_parameters(visitor);
+        }
+        fn visit_parameters_mut(&mut self, visitor: &mut dyn FnMut(ParameterMut)) {
+            self.0.visit_parameters_mut(visitor);
+        }
+        fn forward_batch_in(&self, inputs: Vec<Array1D<3>>, context: ForwardContext) -> (Vec<Array1D<2>>, Vec<Array1D<3>>) {
+            self.1.set(self.1.get() + 1);
+            self.0.forward_batch_in(inputs, context)
+        }
+        fn backward_batch_with(&self, forwards: Vec<Array1D<2>>, forward_data: Vec<Array1D<3>>, outputs: Option<Vec<Array1D<2>>>) -> (Vec<Array1D<3>>, Self::Gradients, Option<Vec<Array1D<3>>>) {
+            self.0.backward_batch_with(forwards, forward_data, outputs)
+        }
+    }
+
+    let mut dense = DenseLayer::<3, 2>::random_with_rng(&mut StdRng::seed_from_u64(0));
+    let mut network = Network::<_, _, Mse, Array1D<2>, Array1D<2>>::new(Batched(dense.clone(), Cell::new(0)));
+    let data = (0..70).map(|x| {
+        let x = x as f32 / 70.0;
+        (Array1D::from([x, 1.0 - x, x * x].as_slice()), Array1D::from([x, -x].as_slice()))
+    }).collect::<Vec<_>>();
+    network.learn_batch(data.clone(), 0.5);
+    assert_eq!(network.layer.1.get(), 70usize.div_ceil(MICRO_BATCH_SIZE));
+
+    let mut gradients = dense.zeroed_gradients();
+    for (input, expected) in data {
+        let (output, forward_data) = dense.forward(input);
+        let mut derivative = Mse.derivative(&output, &expected);
+        derivative *= 1.0 / 70.0;
+        let sample = dense.backward(derivative, forward_data).1;
+        dense.accumulate_gradients(&mut gradients, sample, 1.0);
+    }
+    dense.apply_gradients(gradients, -0.5);
+    for (a, b) in network.layer.0.weights.iter().flatten().chain(network.layer.0.biases.iter()).zip(dense.weights.iter().flatten().chain(dense.biases.iter())) {
+        assert!((a - b).abs() < 1e-6, "{a} {b}");
+    }
+}
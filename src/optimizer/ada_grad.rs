@@ -0,0 +1,33 @@
+use crate::{array::Tensor, layer::Layer};
+
+use super::Optimizer;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct AdaGrad<G> {
+    pub learn_rate: f32,
+    pub epsilon: f32,
+    square_sum: G,
+}
+
+impl<G: Tensor> AdaGrad<G> {
+    pub fn new(learn_rate: f32) -> Self {
+        Self { learn_rate, epsilon: 1e-10, square_sum: G::zeroed() }
+    }
+}
+
+impl<G: Tensor> Optimizer<G> for AdaGrad<G> {
+    fn step<I, L: Layer<I, Gradients = G>>(&mut self, layer: &mut L, mut gradients: G) {
+        for (square_sum, gradients) in self.square_sum.slices_mut().zip(gradients.slices_mut()) {
+            for (square_sum, gradient) in square_sum.iter_mut().zip(gradients) {
+                *square_sum += gradient.powi(2);
+                *gradient /= square_sum.sqrt() + self.epsilon;
+            }
+        }
+        layer.apply_gradients(gradients, -self.learn_rate);
+    }
+}
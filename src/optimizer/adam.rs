@@ -0,0 +1,76 @@
+use crate::{array::Tensor, layer::Layer};
+
+use super::Optimizer;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct Adam<G> {
+    pub learn_rate: f32,
+    pub beta1: f32,
+    pub beta2: f32,
+    pub epsilon: f32,
+    first_moment: G,
+    second_moment: G,
+    steps: i32,
+}
+
+impl<G: Tensor> Adam<G> {
+    pub fn new(learn_rate: f32) -> Self {
+        Self {
+            learn_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+            first_moment: G::zeroed(),
+            second_moment: G::zeroed(),
+            steps: 0,
+        }
+    }
+    /// turns `gradients` into the bias corrected adam update
+    fn update(&mut self, gradients: &mut G) {
+        self.steps += 1;
+        let first_correction = 1.0 - self.beta1.powi(self.steps);
+        let second_correction = 1.0 - self.beta2.powi(self.steps);
+        for ((first, second), gradients) in self.first_moment.slices_mut().zip(self.second_moment.slices_mut()).zip(gradients.slices_mut()) {
+            for ((first, second), gradient) in first.iter_mut().zip(second.iter_mut()).zip(gradients) {
+                *first = self.beta1 * *first + (1.0 - self.beta1) * *gradient;
+                *second = self.beta2 * *second + (1.0 - self.beta2) * gradient.powi(2);
+                *gradient = (*first / first_correction) / ((*second / second_correction).sqrt() + self.epsilon);
+            }
+        }
+    }
+}
+
+impl<G: Tensor> Optimizer<G> for Adam<G> {
+    fn step<I, L: Layer<I, Gradients = G>>(&mut self, layer: &mut L, mut gradients: G) {
+        self.update(&mut gradients);
+        layer.apply_gradients(gradients, -self.learn_rate);
+    }
+}
+
+/// Adam with weight decay applied directly to the weights instead of through the gradients, biases aren't decayed
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct AdamW<G> {
+    pub adam: Adam<G>,
+    pub weight_decay: f32,
+}
+
+impl<G: Tensor> AdamW<G> {
+    pub fn new(learn_rate: f32, weight_decay: f32) -> Self {
+        Self { adam: Adam::new(learn_rate), weight_decay }
+    }
+}
+
+impl<G: Tensor> Optimizer<G> for AdamW<G> {
+    fn step<I, L: Layer<I, Gradients = G>>(&mut self, layer: &mut L, mut gradients: G) {
+        self.adam.update(&mut gradients);
+        layer.decay_weights(1.0 - self.adam.learn_rate * self.weight_decay);
+        layer.apply_gradients(gradients, -self.adam.learn_rate);
+    }
+}
@@ -0,0 +1,80 @@
+use crate::layer::Layer;
+
+pub mod sgd;
+pub mod momentum;
+pub mod adam;
+pub mod rms_prop;
+pub mod ada_grad;
+
+pub trait Optimizer<G> {
+    /// `gradients` are the gradients of one batch, already averaged over its samples
+    fn step<I, L: Layer<I, Gradients = G>>(&mut self, layer: &mut L, gradients: G);
+}
+
+#[test]
+fn adam_fits_line() {
+    use crate::{array::Array1D, cost::Mse, layer::dense::DenseLayer, Network};
+    use adam::Adam;
+
+    let mut network = Network::<_, _, Mse, Array1D<1>, Array1D<1>>::new(DenseLayer::<1, 1>::new());
+    let data = (0..10).map(|x| {
+        let x = x as f32 / 10.0;
+        (Array1D::from([x].as_slice()), Array1D::from([2.0 * x + 1.0].as_slice()))
+    }).collect::<Vec<_>>();
+    let mut optimizer = Adam::new(0.05);
+    for _ in 0..1000 {
+        network.learn_batch_with(data.clone(), &mut optimizer);
+    }
+    assert!((network.layer.weights[0][0] - 2.0).abs() < 0.05);
+    assert!((network.layer.biases[0] - 1.0).abs() < 0.05);
+}
+
+/// weight and bias of a 1x1 dense layer starting at 1 after two steps with both gradients 0.5
+#[cfg(test)]
+fn two_steps(mut optimizer: impl Optimizer<(crate::array::Array2D<1, 1>, crate::array::Array1D<1>)>) -> (f32, f32) {
+    use crate::{array::{Array1D, Array2D}, layer::dense::DenseLayer};
+
+    let mut layer = DenseLayer::<1, 1>::new();
+    layer.weights[0][0] = 1.0;
+    layer.biases[0] = 1.0;
+    for _ in 0..2 {
+        optimizer.step::<Array1D<1>, _>(&mut layer, (Array2D { array: Box::new([[0.5]]) }, Array1D::from([0.5].as_slice())));
+    }
+    (layer.weights[0][0], layer.biases[0])
+}
+
+#[cfg(test)]
+fn assert_close((weight, bias): (f32, f32), expected: (f32, f32)) {
+    assert!((weight - expected.0).abs() < 1e-5, "weight {weight} != {}", expected.0);
+    assert!((bias - expected.1).abs() < 1e-5, "bias {bias} != {}", expected.1);
+}
+
+#[test]
+fn momentum_steps() {
+    // velocity 0.5 then 0.9 * 0.5 + 0.5
+    assert_close(two_steps(momentum::Momentum::new(0.1, 0.9)), (0.855, 0.855));
+}
+
+#[test]
+fn nesterov_steps() {
+    // steps along 0.5 + 0.9 * 0.5 then 0.5 + 0.9 * 0.95
+    assert_close(two_steps(momentum::Nesterov::new(0.1, 0.9)), (0.7695, 0.7695));
+}
+
+#[test]
+fn rms_prop_steps() {
+    // mean squares 0.0025 then 0.004975
+    assert_close(two_steps(rms_prop::RmsProp::new(0.1)), (-0.708_881, -0.708_881));
+}
+
+#[test]
+fn ada_grad_steps() {
+    // square sums 0.25 then 0.5
+    assert_close(two_steps(ada_grad::AdaGrad::new(0.1)), (0.829_289, 0.829_289));
+}
+
+#[test]
+fn adam_w_steps() {
+    // the bias corrected update is 1 both times, only the weight decays by 1 - 0.1 * 0.1 before each step
+    assert_close(two_steps(adam::AdamW::new(0.1, 0.1)), (0.7811, 0.8));
+}
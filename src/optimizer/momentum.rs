@@ -0,0 +1,61 @@
+use crate::{array::Tensor, layer::Layer};
+
+use super::Optimizer;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct Momentum<G> {
+    pub learn_rate: f32,
+    pub momentum: f32,
+    velocity: G,
+}
+
+impl<G: Tensor> Momentum<G> {
+    pub fn new(learn_rate: f32, momentum: f32) -> Self {
+        Self { learn_rate, momentum, velocity: G::zeroed() }
+    }
+}
+
+impl<G: Tensor> Optimizer<G> for Momentum<G> {
+    fn step<I, L: Layer<I, Gradients = G>>(&mut self, layer: &mut L, mut gradients: G) {
+        for (velocity, gradients) in self.velocity.slices_mut().zip(gradients.slices_mut()) {
+            for (velocity, gradient) in velocity.iter_mut().zip(gradients) {
+                *velocity = self.momentum * *velocity + *gradient;
+                *gradient = *velocity;
+            }
+        }
+        layer.apply_gradients(gradients, -self.learn_rate);
+    }
+}
+
+/// Momentum that looks ahead along the velocity before stepping
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct Nesterov<G> {
+    pub learn_rate: f32,
+    pub momentum: f32,
+    velocity: G,
+}
+
+impl<G: Tensor> Nesterov<G> {
+    pub fn new(learn_rate: f32, momentum: f32) -> Self {
+        Self { learn_rate, momentum, velocity: G::zeroed() }
+    }
+}
+
+impl<G: Tensor> Optimizer<G> for Nesterov<G> {
+    fn step<I, L: Layer<I, Gradients = G>>(&mut self, layer: &mut L, mut gradients: G) {
+        for (velocity, gradients) in self.velocity.slices_mut().zip(gradients.slices_mut()) {
+            for (velocity, gradient) in velocity.iter_mut().zip(gradients) {
+                *velocity = self.momentum * *velocity + *gradient;
+                *gradient += self.momentum * *velocity;
+            }
+        }
+        layer.apply_gradients(gradients, -self.learn_rate);
+    }
+}
@@ -0,0 +1,34 @@
+use crate::{array::Tensor, layer::Layer};
+
+use super::Optimizer;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct RmsProp<G> {
+    pub learn_rate: f32,
+    pub decay: f32,
+    pub epsilon: f32,
+    mean_square: G,
+}
+
+impl<G: Tensor> RmsProp<G> {
+    pub fn new(learn_rate: f32) -> Self {
+        Self { learn_rate, decay: 0.99, epsilon: 1e-8, mean_square: G::zeroed() }
+    }
+}
+
+impl<G: Tensor> Optimizer<G> for RmsProp<G> {
+    fn step<I, L: Layer<I, Gradients = G>>(&mut self, layer: &mut L, mut gradients: G) {
+        for (mean_square, gradients) in self.mean_square.slices_mut().zip(gradients.slices_mut()) {
+            for (mean_square, gradient) in mean_square.iter_mut().zip(gradients) {
+                *mean_square = self.decay * *mean_square + (1.0 - self.decay) * gradient.powi(2);
+                *gradient /= mean_square.sqrt() + self.epsilon;
+            }
+        }
+        layer.apply_gradients(gradients, -self.learn_rate);
+    }
+}
@@ -0,0 +1,25 @@
+use crate::layer::Layer;
+
+use super::Optimizer;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sgd {
+    pub learn_rate: f32,
+}
+
+impl Sgd {
+    pub fn new(learn_rate: f32) -> Self {
+        Self { learn_rate }
+    }
+}
+
+impl<G> Optimizer<G> for Sgd {
+    fn step<I, L: Layer<I, Gradients = G>>(&mut self, layer: &mut L, gradients: G) {
+        layer.apply_gradients(gradients, -self.learn_rate);
+    }
+}
@@ -24,8 +24,8 @@ fn main() {
     //     }
     //     cost = 0.0;
     //     for point in data.clone() {
-    //         let out = network.forward(point.0).0;
-    //         cost += Mse::cost(&out, &point.1);
+    //         let out = network.infer(point.0);
+    //         cost += Mse.cost(&out, &point.1);
     //     }
     // }
 
@@ -86,13 +86,14 @@ fn main() {
             );
         }
 
-        shader.set_shader_value(bias1, network.layer.next.next.step.biases[0]);
-        shader.set_shader_value(weights1, network.layer.next.next.step.weights.array.as_ref()[0]);
-        shader.set_shader_value(biases0, *network.layer.step.biases.array.as_ref());
-        shader.set_shader_value(weights00, network.layer.step.weights[0]);
-        shader.set_shader_value(weights01, network.layer.step.weights[1]);
-        shader.set_shader_value(weights02, network.layer.step.weights[2]);
-        shader.set_shader_value(weights03, network.layer.step.weights[3]);
+        let (hidden, output) = (network.layer.get::<0>(), network.layer.get::<2>());
+        shader.set_shader_value(bias1, output.biases[0]);
+        shader.set_shader_value(weights1, output.weights.array.as_ref()[0]);
+        shader.set_shader_value(biases0, *hidden.biases.array.as_ref());
+        shader.set_shader_value(weights00, hidden.weights[0]);
+        shader.set_shader_value(weights01, hidden.weights[1]);
+        shader.set_shader_value(weights02, hidden.weights[2]);
+        shader.set_shader_value(weights03, hidden.weights[3]);
         
         let mut d = rl.begin_drawing(&rt);
         d.begin_shader_mode(&mut shader).draw_rectangle(0, 0, width as i32, height as i32, Color::WHITE);
@@ -105,8 +106,8 @@ fn main() {
         let mut cost = 0.0;
         let mut correct = 0;
         for point in data.clone() {
-            let out = network.forward(point.0).0;
-            cost += Mse::cost(&out, &point.1);
+            let out = network.infer(point.0);
+            cost += Mse.cost(&out, &point.1);
             correct += ((out[0] - point.1[0]).abs() < 0.5) as usize 
         }
         d.draw_text(&format!("{correct}/100 {cost:02}"), 10, 100, 20, Color::WHITE);